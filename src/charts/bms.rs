@@ -0,0 +1,316 @@
+// BMS / BME / BML chart import with keysounds
+// Channels 11-14 (and long notes on 51-54) are the playable lanes, every other
+// key channel is turned into background sound so the song stays complete.
+// Landmines on D1-D4 become mines in the matching lane
+
+use std::collections::HashMap;
+use super::{BackgroundSound, BpmChange, Chart, ChartError, ChartMetadata, ChartNote, NoteKind, Stop, TempoMap, TimeSignature};
+use crate::constants::LANES;
+use crate::types::Beat;
+
+const DEFAULT_BPM: f32 = 130.0;
+const STOP_UNITS_PER_BEAT: f32 = 48.0; // #STOP values count 192nds of a whole note
+
+#[derive(Clone, Copy)]
+struct ChannelEvent {
+    beat: Beat,
+    end: Beat, // Where the event's cell in the measure ends
+    value: u16, // Base 36 object id, 00 never gets here
+}
+
+// How channels 51-54 mark long notes
+#[derive(Clone, Copy, PartialEq)]
+enum LongNoteType {
+    Pairs, // #LNTYPE 1: objects alternate between the start and the end of a note
+    Held, // #LNTYPE 2: every cell the note is held for has an object
+}
+
+pub fn parse_bms(source: &str) -> Result<Chart, ChartError> {
+    let mut metadata = ChartMetadata::default();
+    let mut bpm = DEFAULT_BPM;
+    let mut ln_object = None;
+    let mut ln_type = LongNoteType::Pairs;
+    let mut wav_paths: HashMap<u16, String> = HashMap::new();
+    let mut extended_bpms: HashMap<u16, f32> = HashMap::new();
+    let mut stop_lengths: HashMap<u16, f32> = HashMap::new();
+    let mut measure_lengths: HashMap<usize, f32> = HashMap::new();
+    let mut channel_lines: Vec<(usize, String, String)> = Vec::new(); // (measure, channel, data)
+
+    // #RANDOM blocks always take branch 1, so a chart loads the same way every time
+    let mut skipping: Vec<bool> = Vec::new();
+
+    for line in source.lines().map(str::trim) {
+        let Some(command) = line.strip_prefix('#') else { continue };
+        let (key, value) = split_command(command);
+        let key = key.to_ascii_uppercase();
+
+        match key.as_str() {
+            "IF" => {
+                skipping.push(value.trim() != "1");
+                continue;
+            }
+            "ENDIF" | "END" => {
+                skipping.pop();
+                continue;
+            }
+            _ => {}
+        }
+        if skipping.iter().any(|skip| *skip) {
+            continue;
+        }
+
+        if let Some((measure, channel)) = parse_channel_key(&key) {
+            if channel == "02" {
+                let length = parse_number(value, "measure length")?;
+                measure_lengths.insert(measure, length);
+            } else {
+                channel_lines.push((measure, channel, value.to_string()));
+            }
+            continue;
+        }
+
+        match key.as_str() {
+            "TITLE" => metadata.title = value.to_string(),
+            "ARTIST" => metadata.artist = value.to_string(),
+            "PLAYLEVEL" => metadata.level = value.parse().unwrap_or(0),
+            "DIFFICULTY" => metadata.difficulty = difficulty_name(value),
+            "BPM" => bpm = parse_number(value, "#BPM")?,
+            "LNOBJ" => ln_object = parse_base36(value),
+            "LNTYPE" => {
+                ln_type = match value {
+                    "1" => LongNoteType::Pairs,
+                    "2" => LongNoteType::Held,
+                    other => return Err(ChartError::Parse(format!("#LNTYPE {} is not supported, only 1 and 2", other))),
+                }
+            }
+            _ => {
+                if let Some(id) = key.strip_prefix("WAV").and_then(parse_base36) {
+                    wav_paths.insert(id, value.replace('\\', "/"));
+                } else if let Some(id) = key.strip_prefix("BPM").and_then(parse_base36) {
+                    extended_bpms.insert(id, parse_number(value, "#BPMxx")?);
+                } else if let Some(id) = key.strip_prefix("STOP").and_then(parse_base36) {
+                    stop_lengths.insert(id, parse_number(value, "#STOPxx")?);
+                }
+            }
+        }
+    }
+
+    // Measures can be stretched by channel 02, so work out where each one starts
+    let last_measure = channel_lines.iter().map(|(measure, _, _)| *measure).max().unwrap_or(0);
+    let mut measure_starts = Vec::with_capacity(last_measure + 2);
+    let measure_beats = |measure: &usize| Beat::from_f32(measure_lengths.get(measure).copied().unwrap_or(1.0) * 4.0);
+    let mut time_signatures: Vec<TimeSignature> = Vec::new();
+    let mut beat = Beat::ZERO;
+    for measure in 0..=last_measure + 1 {
+        measure_starts.push(beat);
+        // Odd measure lengths become time signatures (0.75 -> 3/4, 0.875 -> 7/8)
+        let length = measure_beats(&measure);
+        let signature = TimeSignature {
+            beat,
+            numerator: length.numerator() as u32,
+            denominator: 4 * length.denominator() as u32,
+        };
+        let current = time_signatures.last().copied().unwrap_or(TimeSignature::COMMON);
+        if signature.measure_length() != current.measure_length() && length > Beat::ZERO {
+            time_signatures.push(signature);
+        }
+        beat += length;
+    }
+
+    let mut channels: HashMap<String, Vec<ChannelEvent>> = HashMap::new();
+    for (measure, channel, data) in &channel_lines {
+        let length = measure_beats(measure);
+        let events = channels.entry(channel.clone()).or_default();
+        let ids: Vec<Option<u16>> = data
+            .as_bytes()
+            .chunks(2)
+            .map(|pair| std::str::from_utf8(pair).ok().and_then(parse_base36))
+            .collect();
+        for (index, id) in ids.iter().enumerate() {
+            if let Some(value) = id.filter(|value| *value != 0) {
+                let beat = measure_starts[*measure] + length * Beat::new(index as i64, ids.len() as i64);
+                let end = measure_starts[*measure] + length * Beat::new(index as i64 + 1, ids.len() as i64);
+                events.push(ChannelEvent { beat, end, value });
+            }
+        }
+    }
+    for events in channels.values_mut() {
+        events.sort_by_key(|event| event.beat);
+    }
+    let events = |channel: &str| channels.get(channel).cloned().unwrap_or_default();
+
+    // Channel 03 holds BPMs as hex numbers, 08 points at #BPMxx for anything else
+    let mut bpm_changes: Vec<BpmChange> = events("03")
+        .iter()
+        .map(|event| BpmChange { beat: event.beat, bpm: hex_value(event.value) as f32 })
+        .chain(events("08").iter().filter_map(|event| {
+            extended_bpms.get(&event.value).map(|bpm| BpmChange { beat: event.beat, bpm: *bpm })
+        }))
+        .collect();
+    bpm_changes.sort_by_key(|change| change.beat);
+
+    // Stops are measured in beats, so they need the tempo they happen at
+    let tempo_map = TempoMap::new(0.0, bpm, &bpm_changes, &[], &[], &[]);
+    let stops = events("09")
+        .iter()
+        .filter_map(|event| {
+            let length = stop_lengths.get(&event.value)?;
+            Some(Stop {
+                beat: event.beat,
+                duration: length / STOP_UNITS_PER_BEAT * 60.0 / tempo_map.bpm_at(event.beat),
+            })
+        })
+        .collect();
+
+    // Keysounds are stored once and referenced by index
+    let mut keysounds: Vec<String> = Vec::new();
+    let mut keysound_indices: HashMap<u16, usize> = HashMap::new();
+    let mut keysound = |id: u16| -> Option<usize> {
+        let path = wav_paths.get(&id)?;
+        Some(*keysound_indices.entry(id).or_insert_with(|| {
+            keysounds.push(path.clone());
+            keysounds.len() - 1
+        }))
+    };
+
+    let mut notes: Vec<ChartNote> = Vec::new();
+    let mut background_sounds: Vec<BackgroundSound> = Vec::new();
+
+    for lane in 0..LANES.len() {
+        // #LNOBJ marks the end of the previous note in the lane instead of a new note
+        let mut previous: Option<usize> = None;
+        for event in events(&format!("1{}", lane + 1)) {
+            match previous {
+                Some(index) if ln_object == Some(event.value) => {
+                    notes[index].end_beat = Some(event.beat);
+                    previous = None;
+                }
+                None if ln_object == Some(event.value) => {}
+                _ => {
+                    previous = Some(notes.len());
+                    notes.push(ChartNote { beat: event.beat, lane, kind: NoteKind::Tap, end_beat: None, keysound: keysound(event.value) });
+                }
+            }
+        }
+
+        let long_notes = events(&format!("5{}", lane + 1));
+        match ln_type {
+            // #LNTYPE 1 long notes alternate between start and end objects
+            LongNoteType::Pairs => {
+                for pair in long_notes.chunks(2) {
+                    notes.push(ChartNote {
+                        beat: pair[0].beat,
+                        lane,
+                        kind: NoteKind::Tap,
+                        end_beat: pair.get(1).map(|end| end.beat),
+                        keysound: keysound(pair[0].value),
+                    });
+                }
+            }
+            // #LNTYPE 2 long notes run over back to back cells, even across measures, and end with the last one
+            LongNoteType::Held => {
+                let mut held: Option<usize> = None;
+                for event in long_notes {
+                    match held {
+                        Some(index) if notes[index].end_beat == Some(event.beat) => notes[index].end_beat = Some(event.end),
+                        _ => {
+                            held = Some(notes.len());
+                            notes.push(ChartNote {
+                                beat: event.beat,
+                                lane,
+                                kind: NoteKind::Tap,
+                                end_beat: Some(event.end),
+                                keysound: keysound(event.value),
+                            });
+                        }
+                    }
+                }
+            }
+        }
+
+        // The landmine's value is its damage, every mine costs the same here
+        for event in events(&format!("D{}", lane + 1)) {
+            notes.push(ChartNote { beat: event.beat, lane, kind: NoteKind::Mine, end_beat: None, keysound: None });
+        }
+    }
+
+    // Channel 01 is background music, unplayable key channels join it
+    let autoplay_channels = ["01", "15", "16", "18", "19", "55", "56", "58", "59"];
+    for channel in autoplay_channels {
+        for event in events(channel) {
+            if let Some(keysound) = keysound(event.value) {
+                background_sounds.push(BackgroundSound { beat: event.beat, keysound });
+            }
+        }
+    }
+    background_sounds.sort_by_key(|sound| sound.beat);
+
+    Chart {
+        metadata,
+        audio: None,
+        offset: 0.0,
+        bpm,
+        bpm_changes,
+        stops,
+        delays: Vec::new(),
+        time_signatures,
+        scroll_velocities: Vec::new(),
+        keysounds,
+        background_sounds,
+        notes,
+    }
+    .validate()
+}
+
+// "#WAV01 kick.wav" and "#00111:0101" both split into a key and a value
+fn split_command(command: &str) -> (&str, &str) {
+    if let Some((key, value)) = command.split_once(':') {
+        if parse_channel_key(key).is_some() {
+            return (key, value.trim());
+        }
+    }
+    match command.split_once(char::is_whitespace) {
+        Some((key, value)) => (key, value.trim()),
+        None => (command, ""),
+    }
+}
+
+// "00111" -> measure 1, channel "11"
+fn parse_channel_key(key: &str) -> Option<(usize, String)> {
+    if key.len() != 5 || !key.is_char_boundary(3) {
+        return None;
+    }
+    let measure = key[..3].parse().ok()?;
+    Some((measure, key[3..].to_ascii_uppercase()))
+}
+
+fn parse_base36(value: &str) -> Option<u16> {
+    if value.len() != 2 {
+        return None;
+    }
+    u16::from_str_radix(value, 36).ok()
+}
+
+// Channel 03 reuses the pair of digits as hexadecimal
+fn hex_value(base36: u16) -> u16 {
+    (base36 / 36) * 16 + base36 % 36
+}
+
+fn difficulty_name(value: &str) -> String {
+    match value {
+        "1" => "Beginner",
+        "2" => "Normal",
+        "3" => "Hyper",
+        "4" => "Another",
+        "5" => "Insane",
+        other => other,
+    }
+    .to_string()
+}
+
+fn parse_number(value: &str, what: &str) -> Result<f32, ChartError> {
+    value
+        .trim()
+        .parse()
+        .map_err(|_| ChartError::Parse(format!("invalid {} '{}'", what, value)))
+}
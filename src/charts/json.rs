@@ -0,0 +1,12 @@
+// Our own JSON chart format, a direct serde mapping of `Chart`
+
+use super::{Chart, ChartError};
+
+pub fn parse_json(source: &str) -> Result<Chart, ChartError> {
+    let chart: Chart = serde_json::from_str(source)?;
+    chart.validate()
+}
+
+pub fn write_json(chart: &Chart) -> Result<String, ChartError> {
+    Ok(serde_json::to_string_pretty(chart)? + "\n")
+}
@@ -0,0 +1,69 @@
+// Bevy asset loader so charts load through the AssetServer (and hot reload when edited)
+
+use bevy::asset::{io::Reader, AssetLoader, AsyncReadExt, LoadContext};
+use serde::{Deserialize, Serialize};
+use super::{file_extension, parse_chart_bytes, Chart, ChartError, MidiImportOptions};
+
+#[derive(Default)]
+pub struct ChartLoader;
+
+// Per-file options, set with `load_with_settings` or a .meta file
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+pub struct ChartLoaderSettings {
+    pub midi: MidiImportOptions,
+}
+
+impl AssetLoader for ChartLoader {
+    type Asset = Chart;
+    type Settings = ChartLoaderSettings;
+    type Error = ChartError;
+
+    async fn load<'a>(
+        &'a self,
+        reader: &'a mut Reader<'_>,
+        settings: &'a ChartLoaderSettings,
+        load_context: &'a mut LoadContext<'_>,
+    ) -> Result<Chart, ChartError> {
+        let mut bytes = Vec::new();
+        reader.read_to_end(&mut bytes).await?;
+
+        let mut charts = parse_chart_bytes(&bytes, &file_extension(load_context.path()), &settings.midi)?;
+        if charts.is_empty() {
+            return Err(ChartError::Parse("file contains no playable charts".to_string()));
+        }
+
+        // Every difficulty is reachable as a labeled sub-asset, e.g. "song.sm#Hard"
+        for (label, chart) in difficulty_labels(&charts).into_iter().zip(&charts) {
+            load_context.add_labeled_asset(label, chart.clone());
+        }
+
+        Ok(charts.swap_remove(0))
+    }
+
+    fn extensions(&self) -> &[&str] {
+        CHART_EXTENSIONS
+    }
+}
+
+// Every file extension a chart can be loaded from, JSON charts are ".chart.json" so other JSON assets keep their own loaders
+pub const CHART_EXTENSIONS: &[&str] = &["chart.json", "sm", "ssc", "osu", "qua", "bms", "bme", "bml", "mid", "midi"];
+
+// Sub-asset label of each difficulty in a chart file, numbered when two share a name ("Hard", "Hard2"),
+// and by position in the file when it has no name ("Chart1")
+pub fn difficulty_labels(charts: &[Chart]) -> Vec<String> {
+    let mut labels: Vec<String> = Vec::new();
+    for (index, chart) in charts.iter().enumerate() {
+        let name = match chart.metadata.difficulty.as_str() {
+            "" => format!("Chart{}", index + 1),
+            difficulty => difficulty.to_string(),
+        };
+        let mut label = name.clone();
+        let mut duplicate = 1;
+        while labels.contains(&label) {
+            duplicate += 1;
+            label = format!("{}{}", name, duplicate);
+        }
+        labels.push(label);
+    }
+    labels
+}
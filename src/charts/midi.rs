@@ -0,0 +1,152 @@
+// Standard MIDI file to chart converter
+// One track's note-ons become notes, pitch ranges pick the lane and positions snap to a NoteDuration grid
+
+use midly::{MetaMessage, MidiMessage, Smf, Timing, TrackEventKind};
+use serde::{Deserialize, Serialize};
+use super::{BpmChange, Chart, ChartError, ChartMetadata, ChartNote, NoteKind, TimeSignature};
+use crate::constants::LANES;
+use crate::types::{Beat, NoteDuration};
+
+const DEFAULT_BPM: f32 = 120.0;
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(default)]
+pub struct MidiImportOptions {
+    pub track: Option<usize>, // None picks the first track with notes
+    pub lane_pitches: Option<[(u8, u8); LANES.len()]>, // Inclusive pitch range per lane, None splits the track's range evenly
+    pub quantize: NoteDuration,
+}
+
+impl Default for MidiImportOptions {
+    fn default() -> Self {
+        Self {
+            track: None,
+            lane_pitches: None,
+            quantize: NoteDuration::Sixteenth,
+        }
+    }
+}
+
+pub fn parse_midi(bytes: &[u8], options: &MidiImportOptions) -> Result<Chart, ChartError> {
+    let smf = Smf::parse(bytes)?;
+    let ticks_per_beat = match smf.header.timing {
+        Timing::Metrical(ticks) if ticks.as_int() > 0 => ticks.as_int() as i64,
+        Timing::Metrical(_) => return Err(ChartError::Parse("MIDI file has zero ticks per beat".to_string())),
+        Timing::Timecode(..) => {
+            return Err(ChartError::Parse("SMPTE timed MIDI files are not supported".to_string()));
+        }
+    };
+
+    // Tempo and names can live on any track (usually the first), notes on the chosen one
+    let mut tempos: Vec<(u64, f32)> = Vec::new();
+    let mut signatures: Vec<(u64, u8, u8)> = Vec::new(); // (tick, numerator, denominator as a power of 2)
+    let mut track_names: Vec<String> = vec![String::new(); smf.tracks.len()];
+    let mut track_notes: Vec<Vec<(u64, u8)>> = vec![Vec::new(); smf.tracks.len()];
+
+    for (index, track) in smf.tracks.iter().enumerate() {
+        let mut tick: u64 = 0;
+        for event in track {
+            tick += event.delta.as_int() as u64;
+            match event.kind {
+                TrackEventKind::Meta(MetaMessage::Tempo(micros_per_beat)) => {
+                    tempos.push((tick, 60_000_000.0 / micros_per_beat.as_int() as f32));
+                }
+                TrackEventKind::Meta(MetaMessage::TimeSignature(numerator, denominator_power, _, _)) => {
+                    signatures.push((tick, numerator, denominator_power));
+                }
+                TrackEventKind::Meta(MetaMessage::TrackName(name)) => {
+                    track_names[index] = String::from_utf8_lossy(name).trim().to_string();
+                }
+                TrackEventKind::Midi { message: MidiMessage::NoteOn { key, vel }, .. } if vel.as_int() > 0 => {
+                    track_notes[index].push((tick, key.as_int()));
+                }
+                _ => {}
+            }
+        }
+    }
+
+    let track = match options.track {
+        Some(track) if track < smf.tracks.len() => track,
+        Some(track) => {
+            return Err(ChartError::Parse(format!("MIDI file has no track {} ({} tracks)", track, smf.tracks.len())));
+        }
+        None => track_notes
+            .iter()
+            .position(|notes| !notes.is_empty())
+            .ok_or_else(|| ChartError::Parse("MIDI file has no notes".to_string()))?,
+    };
+    let events = &track_notes[track];
+
+    let lane_pitches = options.lane_pitches.unwrap_or_else(|| split_pitch_range(events));
+    let grid = options.quantize.to_beats();
+    let mut notes: Vec<ChartNote> = events
+        .iter()
+        .filter_map(|&(tick, pitch)| {
+            let lane = lane_pitches.iter().position(|(low, high)| (*low..=*high).contains(&pitch))?;
+            let beat = Beat::new(tick as i64, ticks_per_beat).quantize(grid);
+            Some(ChartNote { beat, lane, kind: NoteKind::Tap, end_beat: None, keysound: None })
+        })
+        .collect();
+
+    // Quantizing can stack notes on the same spot, keep one per lane
+    notes.sort_by_key(|note| (note.beat, note.lane));
+    notes.dedup_by_key(|note| (note.beat, note.lane));
+
+    tempos.sort_by_key(|(tick, _)| *tick);
+    let bpm = tempos.first().filter(|(tick, _)| *tick == 0).map_or(DEFAULT_BPM, |(_, bpm)| *bpm);
+    let bpm_changes = tempos
+        .iter()
+        .filter(|(tick, _)| *tick > 0)
+        .map(|&(tick, bpm)| BpmChange { beat: Beat::new(tick as i64, ticks_per_beat), bpm })
+        .collect();
+
+    signatures.sort_by_key(|(tick, _, _)| *tick);
+    let time_signatures = signatures
+        .iter()
+        .filter(|(_, numerator, denominator_power)| *numerator > 0 && *denominator_power < 8)
+        .map(|&(tick, numerator, denominator_power)| TimeSignature {
+            beat: Beat::new(tick as i64, ticks_per_beat),
+            numerator: numerator as u32,
+            denominator: 1 << denominator_power,
+        })
+        .collect();
+
+    let title = [&track_names[0], &track_names[track]]
+        .into_iter()
+        .find(|name| !name.is_empty())
+        .cloned()
+        .unwrap_or_default();
+
+    Chart {
+        metadata: ChartMetadata {
+            title,
+            difficulty: track_names[track].clone(),
+            ..Default::default()
+        },
+        audio: None,
+        offset: 0.0,
+        bpm,
+        bpm_changes,
+        stops: Vec::new(),
+        delays: Vec::new(),
+        time_signatures,
+        scroll_velocities: Vec::new(),
+        keysounds: Vec::new(),
+        background_sounds: Vec::new(),
+        notes,
+    }
+    .validate()
+}
+
+// Divide the pitches a track actually uses into equal bands, lowest pitches on the left lane
+fn split_pitch_range(events: &[(u64, u8)]) -> [(u8, u8); LANES.len()] {
+    let low = events.iter().map(|(_, pitch)| *pitch).min().unwrap_or(0) as usize;
+    let high = events.iter().map(|(_, pitch)| *pitch).max().unwrap_or(127) as usize;
+    let span = high - low + 1;
+
+    std::array::from_fn(|lane| {
+        let start = low + span * lane / LANES.len();
+        let end = low + span * (lane + 1) / LANES.len();
+        (start as u8, end.saturating_sub(1).max(start) as u8)
+    })
+}
@@ -0,0 +1,315 @@
+// Chart model and loaders
+// A chart is one playable difficulty: timing information plus the notes to hit
+
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::path::Path;
+use crate::constants::LANES;
+use crate::types::Beat;
+
+pub mod bms;
+pub mod json;
+pub mod loader;
+pub mod midi;
+pub mod osu;
+pub mod qua;
+pub mod sm;
+mod timed;
+pub mod timing;
+
+pub use loader::{difficulty_labels, ChartLoader, ChartLoaderSettings, CHART_EXTENSIONS};
+pub use midi::MidiImportOptions;
+pub use timing::{BpmChange, Delay, ScrollVelocity, Stop, TempoMap, TimeSignature};
+
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq)]
+#[serde(default)]
+pub struct ChartMetadata {
+    pub title: String,
+    pub artist: String,
+    pub charter: String,
+    pub difficulty: String,
+    pub level: u32,
+}
+
+// What a note asks of the player
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum NoteKind {
+    #[default]
+    Tap, // Hit it (and keep holding until `end_beat` if it has one)
+    Roll, // Hit it, then keep tapping the lane until `end_beat`
+    Lift, // Let go of the lane on it
+    Mine, // Don't be holding the lane when it crosses the target
+    Fake, // Only for show, never judged
+}
+
+impl NoteKind {
+    pub fn is_tap(&self) -> bool {
+        *self == NoteKind::Tap
+    }
+
+    // Notes the player has to play, mines and fakes only pass by
+    pub fn is_judged(&self) -> bool {
+        matches!(self, NoteKind::Tap | NoteKind::Roll | NoteKind::Lift)
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq)]
+pub struct ChartNote {
+    pub beat: Beat, // Absolute beat position, beat 0 lands on `offset`
+    pub lane: usize,
+    #[serde(default, skip_serializing_if = "NoteKind::is_tap")]
+    pub kind: NoteKind,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub end_beat: Option<Beat>, // Where a hold note is released
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub keysound: Option<usize>, // Index into `Chart::keysounds`, played only when the note is hit
+}
+
+impl ChartNote {
+    // Presses and releases the note gets judged on: holds and rolls have their head and tail
+    pub fn judgements(&self) -> u32 {
+        match (self.kind.is_judged(), self.end_beat) {
+            (false, _) => 0,
+            (true, Some(_)) => 2,
+            (true, None) => 1,
+        }
+    }
+}
+
+// A keysound that plays on its own at a beat (BMS background channel)
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq)]
+pub struct BackgroundSound {
+    pub beat: Beat,
+    pub keysound: usize,
+}
+
+#[derive(Asset, TypePath, Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct Chart {
+    #[serde(default)]
+    pub metadata: ChartMetadata,
+    #[serde(default)]
+    pub audio: Option<String>, // Relative to the chart file
+    #[serde(default)]
+    pub offset: f32, // Seconds into the song where beat 0 lands
+    pub bpm: f32, // Tempo at beat 0
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub bpm_changes: Vec<BpmChange>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub stops: Vec<Stop>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub delays: Vec<Delay>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub time_signatures: Vec<TimeSignature>, // 4/4 until the first one
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub scroll_velocities: Vec<ScrollVelocity>, // Imported and exported, but gameplay scrolls notes at a constant NOTE_SPEED
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub keysounds: Vec<String>, // Sample files relative to the chart
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub background_sounds: Vec<BackgroundSound>,
+    pub notes: Vec<ChartNote>,
+}
+
+impl Chart {
+    // Load a chart from disk, picking the parser from the file extension
+    pub fn load(path: impl AsRef<Path>) -> Result<Chart, ChartError> {
+        Chart::load_all(path)?
+            .into_iter()
+            .next()
+            .ok_or_else(|| ChartError::Parse("file contains no playable charts".to_string()))
+    }
+
+    // Load every difficulty stored in a chart file
+    pub fn load_all(path: impl AsRef<Path>) -> Result<Vec<Chart>, ChartError> {
+        let path = path.as_ref();
+        let bytes = std::fs::read(path)?;
+        parse_chart_bytes(&bytes, &file_extension(path), &MidiImportOptions::default())
+    }
+
+    // Parse chart source text in the format named by `extension`, keeping the first difficulty
+    pub fn parse(source: &str, extension: &str) -> Result<Chart, ChartError> {
+        parse_charts(source, extension)?
+            .into_iter()
+            .next()
+            .ok_or_else(|| ChartError::Parse("file contains no playable charts".to_string()))
+    }
+
+    // Write the chart to disk, picking the writer from the file extension
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<(), ChartError> {
+        let path = path.as_ref();
+        std::fs::write(path, write_chart(self, &file_extension(path))?)?;
+        Ok(())
+    }
+
+    pub fn tempo_map(&self) -> TempoMap {
+        TempoMap::new(self.offset, self.bpm, &self.bpm_changes, &self.stops, &self.delays, &self.time_signatures)
+    }
+
+    // Time in seconds (relative to the song start) at which a beat lands
+    pub fn beat_to_seconds(&self, beat: Beat) -> f32 {
+        self.tempo_map().beat_to_seconds(beat)
+    }
+
+    // Check the chart can be played and put the notes in hit order
+    pub fn validate(mut self) -> Result<Chart, ChartError> {
+        let mut bpms = std::iter::once(self.bpm).chain(self.bpm_changes.iter().map(|change| change.bpm));
+        if let Some(bpm) = bpms.find(|bpm| !bpm.is_finite() || *bpm <= 0.0) {
+            return Err(ChartError::Parse(format!("invalid BPM {}", bpm)));
+        }
+        if let Some(stop) = self.stops.iter().find(|stop| !stop.duration.is_finite() || stop.duration < 0.0) {
+            return Err(ChartError::Parse(format!("invalid stop of {}s at beat {}", stop.duration, stop.beat)));
+        }
+        if let Some(delay) = self.delays.iter().find(|delay| !delay.duration.is_finite() || delay.duration < 0.0) {
+            return Err(ChartError::Parse(format!("invalid delay of {}s at beat {}", delay.duration, delay.beat)));
+        }
+        if let Some(signature) = self.time_signatures.iter().find(|signature| signature.numerator == 0 || signature.denominator == 0) {
+            return Err(ChartError::Parse(format!(
+                "invalid time signature {}/{} at beat {}",
+                signature.numerator, signature.denominator, signature.beat
+            )));
+        }
+        if let Some(note) = self.notes.iter().find(|note| note.lane >= LANES.len()) {
+            return Err(ChartError::Parse(format!(
+                "note at beat {} uses lane {} but only {} lanes exist",
+                note.beat,
+                note.lane,
+                LANES.len()
+            )));
+        }
+        if let Some(note) = self.notes.iter().find(|note| note.beat < Beat::ZERO) {
+            return Err(ChartError::Parse(format!("invalid note beat {}", note.beat)));
+        }
+        if let Some(note) = self.notes.iter().find(|note| note.end_beat.is_some_and(|end| end < note.beat)) {
+            return Err(ChartError::Parse(format!("hold at beat {} ends before it starts", note.beat)));
+        }
+        if let Some(note) = self.notes.iter().find(|note| !matches!(note.kind, NoteKind::Tap | NoteKind::Roll) && note.end_beat.is_some()) {
+            return Err(ChartError::Parse(format!("{:?} note at beat {} can't be a hold", note.kind, note.beat)));
+        }
+        if let Some(note) = self.notes.iter().find(|note| note.kind == NoteKind::Roll && note.end_beat.is_none()) {
+            return Err(ChartError::Parse(format!("roll at beat {} has no end", note.beat)));
+        }
+        let keysound_count = self.keysounds.len();
+        let mut keysound_refs = self.notes.iter().filter_map(|note| note.keysound)
+            .chain(self.background_sounds.iter().map(|sound| sound.keysound));
+        if let Some(index) = keysound_refs.find(|index| *index >= keysound_count) {
+            return Err(ChartError::Parse(format!("keysound {} does not exist", index)));
+        }
+
+        self.notes.sort_by_key(|note| (note.beat, note.lane));
+        self.bpm_changes.sort_by_key(|item| item.beat);
+        self.stops.sort_by_key(|item| item.beat);
+        self.delays.sort_by_key(|item| item.beat);
+        self.time_signatures.sort_by_key(|item| item.beat);
+        self.scroll_velocities.sort_by_key(|item| item.beat);
+        self.background_sounds.sort_by_key(|item| item.beat);
+        Ok(self)
+    }
+}
+
+// Parse every difficulty in a chart file's contents, including binary formats
+pub fn parse_chart_bytes(bytes: &[u8], extension: &str, midi_options: &MidiImportOptions) -> Result<Vec<Chart>, ChartError> {
+    match extension {
+        "mid" | "midi" => Ok(vec![midi::parse_midi(bytes, midi_options)?]),
+        _ => parse_charts(&String::from_utf8_lossy(bytes), extension),
+    }
+}
+
+// Parse every difficulty in chart source text, in the format named by `extension`
+pub fn parse_charts(source: &str, extension: &str) -> Result<Vec<Chart>, ChartError> {
+    match extension {
+        "json" | "chart.json" => Ok(vec![json::parse_json(source)?]),
+        "sm" | "ssc" => sm::parse_sm(source),
+        "osu" => Ok(vec![osu::parse_osu(source)?]),
+        "qua" => Ok(vec![qua::parse_qua(source)?]),
+        "bms" | "bme" | "bml" => Ok(vec![bms::parse_bms(source)?]),
+        other => Err(ChartError::UnsupportedFormat(other.to_string())),
+    }
+}
+
+// Serialize a chart in the format named by `extension`
+pub fn write_chart(chart: &Chart, extension: &str) -> Result<String, ChartError> {
+    match extension {
+        "json" | "chart.json" => json::write_json(chart),
+        "sm" => sm::write_sm(std::slice::from_ref(chart)),
+        "osu" => osu::write_osu(chart),
+        other => Err(ChartError::UnsupportedFormat(other.to_string())),
+    }
+}
+
+// Lowercase extension of a chart path, used to pick the parser ("chart.json" for JSON charts)
+pub fn file_extension(path: &Path) -> String {
+    let extension = path.extension()
+        .and_then(|ext| ext.to_str())
+        .unwrap_or_default()
+        .to_ascii_lowercase();
+    let is_chart = path.file_stem()
+        .and_then(|stem| stem.to_str())
+        .is_some_and(|stem| stem.to_ascii_lowercase().ends_with(".chart"));
+    if extension == "json" && is_chart {
+        "chart.json".to_string()
+    } else {
+        extension
+    }
+}
+
+#[derive(Debug)]
+pub enum ChartError {
+    Io(std::io::Error),
+    Json(serde_json::Error),
+    Yaml(serde_yaml::Error),
+    Midi(midly::Error),
+    Parse(String),
+    UnsupportedFormat(String),
+}
+
+impl fmt::Display for ChartError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ChartError::Io(err) => write!(f, "could not read chart: {}", err),
+            ChartError::Json(err) => write!(f, "invalid JSON chart: {}", err),
+            ChartError::Yaml(err) => write!(f, "invalid YAML chart: {}", err),
+            ChartError::Midi(err) => write!(f, "invalid MIDI file: {}", err),
+            ChartError::Parse(message) => write!(f, "invalid chart: {}", message),
+            ChartError::UnsupportedFormat(extension) => {
+                write!(f, "unsupported chart format '.{}'", extension)
+            }
+        }
+    }
+}
+
+impl std::error::Error for ChartError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            ChartError::Io(err) => Some(err),
+            ChartError::Json(err) => Some(err),
+            ChartError::Yaml(err) => Some(err),
+            ChartError::Midi(err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+impl From<std::io::Error> for ChartError {
+    fn from(err: std::io::Error) -> Self {
+        ChartError::Io(err)
+    }
+}
+
+impl From<serde_json::Error> for ChartError {
+    fn from(err: serde_json::Error) -> Self {
+        ChartError::Json(err)
+    }
+}
+
+impl From<serde_yaml::Error> for ChartError {
+    fn from(err: serde_yaml::Error) -> Self {
+        ChartError::Yaml(err)
+    }
+}
+
+impl From<midly::Error> for ChartError {
+    fn from(err: midly::Error) -> Self {
+        ChartError::Midi(err)
+    }
+}
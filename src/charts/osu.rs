@@ -0,0 +1,266 @@
+// osu!mania .osu beatmap import (file format v14)
+// 4K maps use our lanes as they are, other key counts fold their columns onto the 4 lanes
+
+use super::timed::{build_timed_chart, TimedBpm, TimedNote, TimedScrollVelocity};
+use super::{Chart, ChartError, ChartMetadata};
+use crate::constants::LANES;
+use crate::types::Beat;
+
+const MANIA_MODE: u32 = 3;
+const HOLD_NOTE_FLAG: u32 = 128;
+const PLAYFIELD_WIDTH: f32 = 512.0;
+const DEFAULT_METER: u32 = 4;
+
+struct TimingPoint {
+    time: f32, // Milliseconds
+    beat_length: f32, // Milliseconds per beat, or a negative SV percentage when inherited
+    meter: u32,
+    uninherited: bool,
+}
+
+pub fn parse_osu(source: &str) -> Result<Chart, ChartError> {
+    let mut metadata = ChartMetadata::default();
+    let mut audio = None;
+    let mut mode = 0;
+    let mut keys = 0;
+    let mut timing_points = Vec::new();
+    let mut hit_object_lines = Vec::new();
+    let mut section = String::new();
+
+    for line in source.lines().map(str::trim) {
+        if line.is_empty() || line.starts_with("//") {
+            continue;
+        }
+        if line.starts_with('[') && line.ends_with(']') {
+            section = line[1..line.len() - 1].to_string();
+            continue;
+        }
+
+        match section.as_str() {
+            "General" | "Metadata" | "Difficulty" => {
+                let Some((key, value)) = line.split_once(':') else { continue };
+                let value = value.trim();
+                match key.trim() {
+                    "AudioFilename" => audio = Some(value.to_string()),
+                    "Mode" => mode = parse_number(value, "Mode")?,
+                    "Title" => metadata.title = value.to_string(),
+                    "Artist" => metadata.artist = value.to_string(),
+                    "Creator" => metadata.charter = value.to_string(),
+                    "Version" => metadata.difficulty = value.to_string(),
+                    "CircleSize" => keys = parse_number::<f32>(value, "CircleSize")?.round() as usize,
+                    _ => {}
+                }
+            }
+            "TimingPoints" => timing_points.push(parse_timing_point(line)?),
+            "HitObjects" => hit_object_lines.push(line),
+            _ => {}
+        }
+    }
+
+    if mode != MANIA_MODE {
+        return Err(ChartError::Parse(format!("beatmap is mode {}, only osu!mania (mode 3) is supported", mode)));
+    }
+    if keys == 0 {
+        return Err(ChartError::Parse("beatmap has no columns (CircleSize 0)".to_string()));
+    }
+
+    let notes = hit_object_lines
+        .into_iter()
+        .map(|line| parse_hit_object(line, keys))
+        .collect::<Result<Vec<_>, _>>()?;
+    let notes = fold_columns(notes, keys);
+
+    // Uninherited points set the BPM and reset scroll speed, inherited ones scale it
+    // (kept on the chart for export, notes still scroll at a constant speed in game)
+    let bpms = timing_points
+        .iter()
+        .filter(|point| point.uninherited)
+        .map(|point| TimedBpm { time: point.time, bpm: 60_000.0 / point.beat_length, meter: point.meter })
+        .collect();
+    let scroll_velocities = timing_points
+        .iter()
+        .map(|point| TimedScrollVelocity {
+            time: point.time,
+            multiplier: if point.uninherited { 1.0 } else { -100.0 / point.beat_length },
+        })
+        .collect();
+
+    build_timed_chart(metadata, audio, bpms, scroll_velocities, notes)
+}
+
+// time,beatLength,meter,sampleSet,sampleIndex,volume,uninherited,effects
+fn parse_timing_point(line: &str) -> Result<TimingPoint, ChartError> {
+    let fields: Vec<&str> = line.split(',').map(str::trim).collect();
+    if fields.len() < 2 {
+        return Err(ChartError::Parse(format!("invalid timing point '{}'", line)));
+    }
+
+    let beat_length: f32 = parse_number(fields[1], "timing point beat length")?;
+    // Old files leave out the uninherited flag, a negative beat length marks an inherited point
+    let uninherited = match fields.get(6) {
+        Some(flag) => *flag == "1",
+        None => beat_length > 0.0,
+    };
+    if uninherited && beat_length <= 0.0 {
+        return Err(ChartError::Parse(format!("invalid beat length in timing point '{}'", line)));
+    }
+
+    let meter = match fields.get(2) {
+        Some(meter) => parse_number(meter, "timing point meter")?,
+        None => DEFAULT_METER,
+    };
+
+    Ok(TimingPoint {
+        time: parse_number(fields[0], "timing point time")?,
+        beat_length,
+        meter: meter.max(1),
+        uninherited,
+    })
+}
+
+// x,y,time,type,hitSound,objectParams,hitSample - holds keep their end time in objectParams
+fn parse_hit_object(line: &str, keys: usize) -> Result<TimedNote, ChartError> {
+    let fields: Vec<&str> = line.split(',').map(str::trim).collect();
+    if fields.len() < 5 {
+        return Err(ChartError::Parse(format!("invalid hit object '{}'", line)));
+    }
+
+    let x: f32 = parse_number(fields[0], "hit object x")?;
+    let column = ((x * keys as f32 / PLAYFIELD_WIDTH).floor().max(0.0) as usize).min(keys - 1);
+    let object_type: u32 = parse_number(fields[3], "hit object type")?;
+
+    let end_time = if object_type & HOLD_NOTE_FLAG != 0 {
+        let params = fields.get(5).copied().unwrap_or_default();
+        let end = params.split(':').next().unwrap_or_default();
+        Some(parse_number(end, "hold end time")?)
+    } else {
+        None
+    };
+
+    Ok(TimedNote {
+        lane: column,
+        time: parse_number(fields[2], "hit object time")?,
+        end_time,
+    })
+}
+
+// Spread `keys` columns evenly over the lanes (7K: 0 0 1 1 2 2 3). Notes folded onto a lane that
+// already has a note at that time, or a hold still going, are dropped
+fn fold_columns(mut notes: Vec<TimedNote>, keys: usize) -> Vec<TimedNote> {
+    if keys == LANES.len() {
+        return notes;
+    }
+    notes.sort_by(|a, b| a.time.total_cmp(&b.time));
+
+    let mut busy_until = [f32::MIN; LANES.len()]; // Time each lane is free again
+    let mut folded = Vec::with_capacity(notes.len());
+    for mut note in notes {
+        note.lane = note.lane * LANES.len() / keys;
+        if note.time <= busy_until[note.lane] {
+            continue;
+        }
+        busy_until[note.lane] = note.end_time.unwrap_or(note.time);
+        folded.push(note);
+    }
+    folded
+}
+
+fn parse_number<T: std::str::FromStr>(value: &str, what: &str) -> Result<T, ChartError> {
+    value
+        .trim()
+        .parse()
+        .map_err(|_| ChartError::Parse(format!("invalid {} '{}'", what, value)))
+}
+
+const MIN_SCROLL_VELOCITY: f32 = 0.01; // osu! clamps slower speeds anyway
+
+pub fn write_osu(chart: &Chart) -> Result<String, ChartError> {
+    let tempo_map = chart.tempo_map();
+    let time_ms = |beat: Beat| tempo_map.beat_to_seconds(beat) * 1000.0;
+    let velocity_at = |beat: Beat| {
+        chart
+            .scroll_velocities
+            .iter()
+            .rev()
+            .find(|velocity| velocity.beat <= beat)
+            .map_or(1.0, |velocity| velocity.multiplier)
+    };
+
+    // (time, uninherited, line) - uninherited points reset the scroll speed, so the current one is repeated after them
+    let mut timing_points: Vec<(f32, bool, String)> = Vec::new();
+    let mut add_bpm = |time: f32, beat: Beat| {
+        let beat_length = 60_000.0 / tempo_map.bpm_at(beat);
+        let meter = tempo_map.time_signature_at(beat).measure_length().to_f32().round().max(1.0);
+        timing_points.push((time, true, format!("{},{},{},1,0,100,1,0", time, beat_length, meter)));
+        let velocity = velocity_at(beat);
+        if velocity != 1.0 {
+            timing_points.push((time, false, inherited_point(time, velocity)));
+        }
+    };
+    add_bpm(time_ms(Beat::ZERO), Beat::ZERO);
+    for change in &chart.bpm_changes {
+        add_bpm(time_ms(change.beat), change.beat);
+    }
+    // osu! has no stops or delays, so the beat grid restarts once they are over
+    for stop in &chart.stops {
+        add_bpm(time_ms(stop.beat) + stop.duration * 1000.0, stop.beat);
+    }
+    for delay in &chart.delays {
+        add_bpm(time_ms(delay.beat), delay.beat);
+    }
+    // Every uninherited point starts a new measure, so signatures need one too
+    for signature in &chart.time_signatures {
+        add_bpm(time_ms(signature.beat), signature.beat);
+    }
+    for velocity in &chart.scroll_velocities {
+        timing_points.push((time_ms(velocity.beat), false, inherited_point(time_ms(velocity.beat), velocity.multiplier)));
+    }
+    timing_points.sort_by(|a, b| a.0.total_cmp(&b.0).then(b.1.cmp(&a.1)));
+    timing_points.dedup();
+
+    // osu!mania has no mines or fakes, so they're left out (rolls become holds and lifts taps)
+    let hit_objects: Vec<String> = chart
+        .notes
+        .iter()
+        .filter(|note| note.kind.is_judged())
+        .map(|note| {
+            let x = (PLAYFIELD_WIDTH * (2 * note.lane + 1) as f32 / (2 * LANES.len()) as f32).floor();
+            let time = time_ms(note.beat).round();
+            match note.end_beat {
+                Some(end) => format!("{},192,{},{},0,{}:0:0:0:0:", x, time, HOLD_NOTE_FLAG, time_ms(end).round()),
+                None => format!("{},192,{},1,0,0:0:0:0:", x, time),
+            }
+        })
+        .collect();
+
+    let metadata = &chart.metadata;
+    let mut beatmap = String::from("osu file format v14\n\n");
+    beatmap.push_str(&format!(
+        "[General]\nAudioFilename: {}\nAudioLeadIn: 0\nPreviewTime: -1\nMode: {}\n\n",
+        chart.audio.as_deref().unwrap_or_default(),
+        MANIA_MODE
+    ));
+    beatmap.push_str(&format!(
+        "[Metadata]\nTitle:{}\nTitleUnicode:{}\nArtist:{}\nArtistUnicode:{}\nCreator:{}\nVersion:{}\n\n",
+        metadata.title, metadata.title, metadata.artist, metadata.artist, metadata.charter, metadata.difficulty
+    ));
+    beatmap.push_str(&format!(
+        "[Difficulty]\nHPDrainRate:8\nCircleSize:{}\nOverallDifficulty:8\nApproachRate:5\nSliderMultiplier:1.4\nSliderTickRate:1\n\n",
+        LANES.len()
+    ));
+    beatmap.push_str("[TimingPoints]\n");
+    for (_, _, line) in &timing_points {
+        beatmap.push_str(line);
+        beatmap.push('\n');
+    }
+    beatmap.push_str("\n[HitObjects]\n");
+    for line in &hit_objects {
+        beatmap.push_str(line);
+        beatmap.push('\n');
+    }
+    Ok(beatmap)
+}
+
+fn inherited_point(time: f32, multiplier: f32) -> String {
+    format!("{},{},4,1,0,100,0,0", time, -100.0 / multiplier.max(MIN_SCROLL_VELOCITY))
+}
@@ -0,0 +1,117 @@
+// Quaver .qua chart import (YAML)
+
+use serde::Deserialize;
+use super::timed::{build_timed_chart, TimedBpm, TimedNote, TimedScrollVelocity};
+use super::{Chart, ChartError, ChartMetadata};
+use crate::constants::LANES;
+
+const KEYS_4: &str = "Keys4";
+const TRIPLE: &str = "Triple";
+
+// Quaver leaves out fields holding their default value, so everything falls back to one
+#[derive(Deserialize)]
+#[serde(rename_all = "PascalCase", default)]
+struct Qua {
+    audio_file: Option<String>,
+    mode: String,
+    title: String,
+    artist: String,
+    creator: String,
+    difficulty_name: String,
+    initial_scroll_velocity: f32,
+    timing_points: Vec<QuaTimingPoint>,
+    slider_velocities: Vec<QuaSliderVelocity>,
+    hit_objects: Vec<QuaHitObject>,
+}
+
+impl Default for Qua {
+    fn default() -> Self {
+        Self {
+            audio_file: None,
+            mode: String::new(),
+            title: String::new(),
+            artist: String::new(),
+            creator: String::new(),
+            difficulty_name: String::new(),
+            initial_scroll_velocity: 1.0,
+            timing_points: Vec::new(),
+            slider_velocities: Vec::new(),
+            hit_objects: Vec::new(),
+        }
+    }
+}
+
+#[derive(Deserialize, Default)]
+#[serde(rename_all = "PascalCase", default)]
+struct QuaTimingPoint {
+    start_time: f32,
+    bpm: f32,
+    signature: String, // "Quadruple" (the default, left out) or "Triple"
+}
+
+#[derive(Deserialize, Default)]
+#[serde(rename_all = "PascalCase", default)]
+struct QuaSliderVelocity {
+    start_time: f32,
+    multiplier: f32,
+}
+
+#[derive(Deserialize, Default)]
+#[serde(rename_all = "PascalCase", default)]
+struct QuaHitObject {
+    start_time: f32,
+    lane: usize, // 1-based
+    end_time: f32, // 0 unless the note is held
+}
+
+pub fn parse_qua(source: &str) -> Result<Chart, ChartError> {
+    let qua: Qua = serde_yaml::from_str(source)?;
+
+    if qua.mode != KEYS_4 {
+        return Err(ChartError::Parse(format!("Quaver mode '{}' is not supported, only {}", qua.mode, KEYS_4)));
+    }
+
+    let notes = qua
+        .hit_objects
+        .iter()
+        .map(|object| {
+            if object.lane == 0 || object.lane > LANES.len() {
+                return Err(ChartError::Parse(format!("hit object at {}ms uses lane {}", object.start_time, object.lane)));
+            }
+            Ok(TimedNote {
+                lane: object.lane - 1,
+                time: object.start_time,
+                end_time: (object.end_time > 0.0).then_some(object.end_time),
+            })
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let bpms = qua
+        .timing_points
+        .iter()
+        .map(|point| TimedBpm {
+            time: point.start_time,
+            bpm: point.bpm,
+            meter: if point.signature == TRIPLE { 3 } else { 4 },
+        })
+        .collect();
+
+    // Slider velocities are only kept on the chart for export, notes still scroll at a constant speed in game
+    let initial_velocity = TimedScrollVelocity { time: f32::MIN, multiplier: qua.initial_scroll_velocity };
+    let scroll_velocities = std::iter::once(initial_velocity)
+        .chain(qua.slider_velocities.iter().map(|velocity| TimedScrollVelocity {
+            time: velocity.start_time,
+            multiplier: velocity.multiplier,
+        }))
+        .collect();
+
+    let metadata = ChartMetadata {
+        title: qua.title,
+        artist: qua.artist,
+        charter: qua.creator,
+        difficulty: qua.difficulty_name,
+        level: 0,
+    };
+
+    build_timed_chart(metadata, qua.audio_file, bpms, scroll_velocities, notes)
+}
@@ -0,0 +1,452 @@
+// StepMania .sm / .ssc simfile import
+// Only dance-single charts are kept, their 4 panels map straight onto our lanes
+
+use super::{BpmChange, Chart, ChartError, ChartMetadata, ChartNote, Delay, NoteKind, Stop, TimeSignature};
+use crate::constants::LANES;
+use crate::types::Beat;
+
+const DANCE_SINGLE: &str = "dance-single";
+
+// Timing tags, song-wide or overridden per chart in .ssc files
+#[derive(Clone, Default)]
+struct SmTiming {
+    offset: f32, // Already converted to our sign convention
+    bpms: Vec<BpmChange>,
+    stops: Vec<Stop>,
+    delays: Vec<Delay>,
+    time_signatures: Vec<TimeSignature>,
+}
+
+// Everything known about one chart before its note data
+#[derive(Clone, Default)]
+struct StepsHeader {
+    steps_type: String,
+    credit: String,
+    difficulty: String,
+    meter: String,
+    timing: SmTiming,
+}
+
+pub fn parse_sm(source: &str) -> Result<Vec<Chart>, ChartError> {
+    let mut song = ChartMetadata::default();
+    let mut music = None;
+    let mut timing = SmTiming::default();
+    let mut charts = Vec::new();
+    // .ssc charts are a run of tags opened by #NOTEDATA and closed by their #NOTES
+    let mut note_data: Option<StepsHeader> = None;
+
+    for (tag, value) in parse_tags(source) {
+        if tag == "NOTES" || tag == "NOTES2" {
+            let (header, data) = match note_data.take() {
+                Some(header) => (header, value.as_str()),
+                None => parse_sm_notes_header(&value, &timing)?,
+            };
+            if header.steps_type == DANCE_SINGLE {
+                charts.push(build_chart(&song, &music, header, data)?);
+            }
+            continue;
+        }
+
+        match (tag.as_str(), note_data.as_mut()) {
+            ("NOTEDATA", _) => {
+                note_data = Some(StepsHeader {
+                    timing: timing.clone(),
+                    ..Default::default()
+                })
+            }
+            ("STEPSTYPE", Some(header)) => header.steps_type = value,
+            ("DIFFICULTY", Some(header)) => header.difficulty = value,
+            ("METER", Some(header)) => header.meter = value,
+            ("CREDIT", Some(header)) => header.credit = value,
+            ("DESCRIPTION", Some(header)) if header.credit.is_empty() => header.credit = value,
+            ("OFFSET", Some(header)) => header.timing.offset = parse_offset(&value)?,
+            ("BPMS", Some(header)) => header.timing.bpms = parse_bpms(&value)?,
+            ("STOPS" | "FREEZES", Some(header)) => header.timing.stops = parse_stops(&value)?,
+            ("DELAYS", Some(header)) => header.timing.delays = parse_delays(&value)?,
+            ("TIMESIGNATURES", Some(header)) => header.timing.time_signatures = parse_time_signatures(&value)?,
+            ("TITLE", None) => song.title = value,
+            ("ARTIST", None) => song.artist = value,
+            ("CREDIT", None) => song.charter = value,
+            ("MUSIC", None) if !value.is_empty() => music = Some(value),
+            ("OFFSET", None) => timing.offset = parse_offset(&value)?,
+            ("BPMS", None) => timing.bpms = parse_bpms(&value)?,
+            ("STOPS" | "FREEZES", None) => timing.stops = parse_stops(&value)?,
+            ("DELAYS", None) => timing.delays = parse_delays(&value)?,
+            ("TIMESIGNATURES", None) => timing.time_signatures = parse_time_signatures(&value)?,
+            _ => {}
+        }
+    }
+
+    if charts.is_empty() {
+        return Err(ChartError::Parse(format!("simfile has no {} charts", DANCE_SINGLE)));
+    }
+    Ok(charts)
+}
+
+// Split a simfile into (TAG, value) pairs, tolerating a missing ';' before the next tag
+fn parse_tags(source: &str) -> Vec<(String, String)> {
+    let cleaned = strip_comments(source);
+
+    let mut tags = Vec::new();
+    let mut rest = cleaned.as_str();
+    while let Some(start) = rest.find('#') {
+        rest = &rest[start + 1..];
+        let end = [rest.find(';'), rest.find("\n#")]
+            .into_iter()
+            .flatten()
+            .min()
+            .unwrap_or(rest.len());
+
+        if let Some((tag, value)) = rest[..end].split_once(':') {
+            tags.push((tag.trim().to_ascii_uppercase(), value.trim().to_string()));
+        }
+        rest = &rest[end..];
+        rest = rest.strip_prefix(';').unwrap_or(rest);
+    }
+    tags
+}
+
+// Drop `//` comments. Between tags everything after them goes, inside a tag's value only when they start the
+// line or follow a space or ',' (measure comments), so URLs like `http://` in #TITLE or #CREDIT survive
+fn strip_comments(source: &str) -> String {
+    let mut cleaned = String::with_capacity(source.len());
+    let mut in_value = false; // Between a tag's '#' and its ';'
+    for line in source.lines() {
+        // A tag starting on a new line ends a value that was missing its ';'
+        if line.trim_start().starts_with('#') {
+            in_value = false;
+        }
+        let mut end = line.len();
+        for (index, character) in line.char_indices() {
+            match character {
+                '#' if !in_value => in_value = true,
+                ';' if in_value => in_value = false,
+                '/' if line[index..].starts_with("//") => {
+                    let before = &line[..index];
+                    let comment = !in_value || before.trim().is_empty() || before.ends_with([' ', '\t', ',']);
+                    if comment {
+                        end = index;
+                        break;
+                    }
+                }
+                _ => {}
+            }
+        }
+        cleaned.push_str(&line[..end]);
+        cleaned.push('\n');
+    }
+    cleaned
+}
+
+// .sm packs the chart header into the #NOTES value: type:description:difficulty:meter:radar:data
+fn parse_sm_notes_header<'a>(value: &'a str, timing: &SmTiming) -> Result<(StepsHeader, &'a str), ChartError> {
+    let fields: Vec<&str> = value.splitn(6, ':').collect();
+    if fields.len() != 6 {
+        return Err(ChartError::Parse("#NOTES needs 6 ':' separated fields".to_string()));
+    }
+
+    let header = StepsHeader {
+        steps_type: fields[0].trim().to_string(),
+        credit: fields[1].trim().to_string(),
+        difficulty: fields[2].trim().to_string(),
+        meter: fields[3].trim().to_string(),
+        timing: timing.clone(),
+    };
+    Ok((header, fields[5]))
+}
+
+fn build_chart(
+    song: &ChartMetadata,
+    music: &Option<String>,
+    header: StepsHeader,
+    data: &str,
+) -> Result<Chart, ChartError> {
+    let mut bpms = header.timing.bpms;
+    if bpms.is_empty() {
+        return Err(ChartError::Parse("simfile has no #BPMS".to_string()));
+    }
+    bpms.sort_by_key(|change| change.beat);
+    let initial = bpms.remove(0);
+
+    let charter = if header.credit.is_empty() { song.charter.clone() } else { header.credit };
+
+    Chart {
+        metadata: ChartMetadata {
+            title: song.title.clone(),
+            artist: song.artist.clone(),
+            charter,
+            difficulty: header.difficulty,
+            level: header.meter.parse().unwrap_or(0),
+        },
+        audio: music.clone(),
+        offset: header.timing.offset,
+        bpm: initial.bpm,
+        bpm_changes: bpms,
+        stops: header.timing.stops,
+        delays: header.timing.delays,
+        time_signatures: header.timing.time_signatures,
+        scroll_velocities: Vec::new(),
+        keysounds: Vec::new(),
+        background_sounds: Vec::new(),
+        notes: parse_measures(data)?,
+    }
+    .validate()
+}
+
+// Note data is a list of ',' separated measures, each split evenly into rows (4ths, 8ths, ... 192nds)
+fn parse_measures(data: &str) -> Result<Vec<ChartNote>, ChartError> {
+    let mut notes: Vec<ChartNote> = Vec::new();
+    let mut open_holds: [Option<usize>; LANES.len()] = [None; LANES.len()]; // Index of each lane's unfinished hold
+
+    for (measure, block) in data.split(',').enumerate() {
+        let rows: Vec<&str> = block.split_whitespace().collect();
+        for (row_index, row) in rows.iter().enumerate() {
+            if row.chars().count() != LANES.len() {
+                return Err(ChartError::Parse(format!(
+                    "measure {} row '{}' does not have {} columns",
+                    measure,
+                    row,
+                    LANES.len()
+                )));
+            }
+
+            let beat = Beat::from_measure(measure as i64, row_index as i64, rows.len() as i64);
+            for (lane, symbol) in row.chars().enumerate() {
+                match symbol {
+                    '1' => notes.push(ChartNote { beat, lane, kind: NoteKind::Tap, end_beat: None, keysound: None }),
+                    'L' => notes.push(ChartNote { beat, lane, kind: NoteKind::Lift, end_beat: None, keysound: None }),
+                    'M' => notes.push(ChartNote { beat, lane, kind: NoteKind::Mine, end_beat: None, keysound: None }),
+                    'F' => notes.push(ChartNote { beat, lane, kind: NoteKind::Fake, end_beat: None, keysound: None }),
+                    // Hold and roll heads, closed by the next '3' in the lane
+                    '2' | '4' => {
+                        if let Some(index) = open_holds[lane] {
+                            return Err(ChartError::Parse(format!(
+                                "hold in lane {} at beat {} starts before the one at beat {} ends",
+                                lane, beat, notes[index].beat
+                            )));
+                        }
+                        open_holds[lane] = Some(notes.len());
+                        let kind = if symbol == '4' { NoteKind::Roll } else { NoteKind::Tap };
+                        notes.push(ChartNote { beat, lane, kind, end_beat: None, keysound: None });
+                    }
+                    '3' => {
+                        let Some(index) = open_holds[lane].take() else {
+                            return Err(ChartError::Parse(format!("hold end in lane {} at beat {} has no hold to close", lane, beat)));
+                        };
+                        notes[index].end_beat = Some(beat);
+                    }
+                    _ => {}
+                }
+            }
+        }
+    }
+
+    if let Some((lane, index)) = open_holds.iter().enumerate().find_map(|(lane, index)| Some((lane, (*index)?))) {
+        return Err(ChartError::Parse(format!("hold in lane {} at beat {} never ends", lane, notes[index].beat)));
+    }
+    Ok(notes)
+}
+
+// StepMania's offset is the negated time of beat 0
+fn parse_offset(value: &str) -> Result<f32, ChartError> {
+    value
+        .trim()
+        .parse::<f32>()
+        .map(|offset| -offset)
+        .map_err(|_| ChartError::Parse(format!("invalid #OFFSET '{}'", value)))
+}
+
+// "beat=value,beat=value" lists used by #BPMS, #STOPS and #DELAYS
+fn parse_beat_pairs(value: &str, tag: &str) -> Result<Vec<(f32, f32)>, ChartError> {
+    value
+        .split(',')
+        .map(str::trim)
+        .filter(|entry| !entry.is_empty())
+        .map(|entry| {
+            entry
+                .split_once('=')
+                .and_then(|(beat, value)| Some((beat.trim().parse().ok()?, value.trim().parse().ok()?)))
+                .ok_or_else(|| ChartError::Parse(format!("invalid #{} entry '{}'", tag, entry)))
+        })
+        .collect()
+}
+
+fn parse_bpms(value: &str) -> Result<Vec<BpmChange>, ChartError> {
+    Ok(parse_beat_pairs(value, "BPMS")?
+        .into_iter()
+        .map(|(beat, bpm)| BpmChange { beat: Beat::from_f32(beat), bpm })
+        .collect())
+}
+
+fn parse_stops(value: &str) -> Result<Vec<Stop>, ChartError> {
+    Ok(parse_beat_pairs(value, "STOPS")?
+        .into_iter()
+        .map(|(beat, duration)| Stop { beat: Beat::from_f32(beat), duration })
+        .collect())
+}
+
+fn parse_delays(value: &str) -> Result<Vec<Delay>, ChartError> {
+    Ok(parse_beat_pairs(value, "DELAYS")?
+        .into_iter()
+        .map(|(beat, duration)| Delay { beat: Beat::from_f32(beat), duration })
+        .collect())
+}
+
+// "beat=numerator=denominator,..."
+fn parse_time_signatures(value: &str) -> Result<Vec<TimeSignature>, ChartError> {
+    value
+        .split(',')
+        .map(str::trim)
+        .filter(|entry| !entry.is_empty())
+        .map(|entry| {
+            parse_time_signature(entry)
+                .ok_or_else(|| ChartError::Parse(format!("invalid #TIMESIGNATURES entry '{}'", entry)))
+        })
+        .collect()
+}
+
+fn parse_time_signature(entry: &str) -> Option<TimeSignature> {
+    let fields: Vec<&str> = entry.split('=').map(str::trim).collect();
+    let [beat, numerator, denominator] = fields[..] else {
+        return None;
+    };
+    Some(TimeSignature {
+        beat: Beat::from_f32(beat.parse().ok()?),
+        numerator: numerator.parse().ok()?,
+        denominator: denominator.parse().ok()?,
+    })
+}
+
+// Rows per measure StepMania understands, coarsest first
+const MEASURE_ROWS: [usize; 10] = [4, 8, 12, 16, 24, 32, 48, 64, 96, 192];
+const TICKS_PER_BEAT: i64 = 48; // 192nd notes
+
+// Write charts as one .sm simfile; StepMania keeps timing per song, so every chart must share it
+pub fn write_sm(charts: &[Chart]) -> Result<String, ChartError> {
+    let Some(song) = charts.first() else {
+        return Err(ChartError::Parse("no charts to write".to_string()));
+    };
+    let shares_timing = |chart: &Chart| {
+        chart.offset == song.offset
+            && chart.bpm == song.bpm
+            && chart.bpm_changes == song.bpm_changes
+            && chart.stops == song.stops
+            && chart.delays == song.delays
+            && chart.time_signatures == song.time_signatures
+    };
+    if !charts.iter().all(shares_timing) {
+        return Err(ChartError::Parse("all charts in a simfile must share their timing".to_string()));
+    }
+
+    // Timing beats are written with 3 decimals, which only reads back exactly for beats on the 192nd grid
+    let beat = |beat: Beat| -> Result<String, ChartError> {
+        to_ticks(beat)?;
+        Ok(format!("{:.3}", beat.to_f64()))
+    };
+    let bpms = std::iter::once(BpmChange { beat: Beat::ZERO, bpm: song.bpm })
+        .chain(song.bpm_changes.iter().copied())
+        .map(|change| Ok(format!("{}={}", beat(change.beat)?, change.bpm)))
+        .collect::<Result<Vec<_>, ChartError>>()?;
+    let stops = song
+        .stops
+        .iter()
+        .map(|stop| Ok(format!("{}={}", beat(stop.beat)?, stop.duration)))
+        .collect::<Result<Vec<_>, ChartError>>()?;
+    let delays = song
+        .delays
+        .iter()
+        .map(|delay| Ok(format!("{}={}", beat(delay.beat)?, delay.duration)))
+        .collect::<Result<Vec<_>, ChartError>>()?;
+    let time_signatures = song
+        .time_signatures
+        .iter()
+        .map(|signature| Ok(format!("{}={}={}", beat(signature.beat)?, signature.numerator, signature.denominator)))
+        .collect::<Result<Vec<_>, ChartError>>()?;
+    // Adding 0.0 turns a rounded -0.0 into 0.0
+    let offset = (-song.offset * 1000.0).round() / 1000.0 + 0.0;
+
+    let mut simfile = String::new();
+    simfile.push_str(&format!("#TITLE:{};\n", song.metadata.title));
+    simfile.push_str(&format!("#ARTIST:{};\n", song.metadata.artist));
+    simfile.push_str(&format!("#CREDIT:{};\n", song.metadata.charter));
+    simfile.push_str(&format!("#MUSIC:{};\n", song.audio.as_deref().unwrap_or_default()));
+    simfile.push_str(&format!("#OFFSET:{:.3};\n", offset));
+    simfile.push_str(&format!("#BPMS:{};\n", bpms.join(",")));
+    simfile.push_str(&format!("#STOPS:{};\n", stops.join(",")));
+    if !delays.is_empty() {
+        simfile.push_str(&format!("#DELAYS:{};\n", delays.join(",")));
+    }
+    if !time_signatures.is_empty() {
+        simfile.push_str(&format!("#TIMESIGNATURES:{};\n", time_signatures.join(",")));
+    }
+
+    for chart in charts {
+        simfile.push_str(&format!(
+            "\n#NOTES:\n     {}:\n     {}:\n     {}:\n     {}:\n     0,0,0,0,0:\n{};\n",
+            DANCE_SINGLE,
+            chart.metadata.charter,
+            chart.metadata.difficulty,
+            chart.metadata.level,
+            write_measures(&chart.notes)?
+        ));
+    }
+    Ok(simfile)
+}
+
+// Lay notes out measure by measure, each measure using the coarsest row count that fits it
+// Beats off the 192nd grid and two symbols in one cell can't be written, so they fail instead of moving notes
+fn write_measures(notes: &[ChartNote]) -> Result<String, ChartError> {
+    let tick = |beat: Beat| to_ticks(beat).map(|ticks| ticks as usize);
+    let mut events: Vec<(usize, usize, char)> = Vec::new(); // (tick, lane, symbol)
+    for note in notes {
+        match (note.kind, note.end_beat) {
+            (NoteKind::Lift, _) => events.push((tick(note.beat)?, note.lane, 'L')),
+            (NoteKind::Mine, _) => events.push((tick(note.beat)?, note.lane, 'M')),
+            (NoteKind::Fake, _) => events.push((tick(note.beat)?, note.lane, 'F')),
+            (NoteKind::Tap | NoteKind::Roll, Some(end)) => {
+                events.push((tick(note.beat)?, note.lane, if note.kind == NoteKind::Roll { '4' } else { '2' }));
+                events.push((tick(end)?, note.lane, '3'));
+            }
+            (NoteKind::Tap | NoteKind::Roll, None) => events.push((tick(note.beat)?, note.lane, '1')),
+        }
+    }
+
+    let ticks_per_measure = TICKS_PER_BEAT as usize * 4;
+    let measure_count = events.iter().map(|(tick, _, _)| tick / ticks_per_measure + 1).max().unwrap_or(1);
+
+    let measures = (0..measure_count)
+        .map(|measure| {
+            let in_measure: Vec<(usize, usize, char)> = events
+                .iter()
+                .filter(|(tick, _, _)| tick / ticks_per_measure == measure)
+                .map(|&(tick, lane, symbol)| (tick % ticks_per_measure, lane, symbol))
+                .collect();
+            let rows = MEASURE_ROWS
+                .into_iter()
+                .find(|rows| in_measure.iter().all(|(tick, _, _)| tick % (ticks_per_measure / rows) == 0))
+                .unwrap_or(ticks_per_measure);
+
+            let mut grid = vec![['0'; LANES.len()]; rows];
+            for (tick, lane, symbol) in in_measure {
+                let cell = &mut grid[tick / (ticks_per_measure / rows)][lane];
+                if *cell != '0' {
+                    let beat = Beat::new((measure * ticks_per_measure + tick) as i64, TICKS_PER_BEAT);
+                    return Err(ChartError::Parse(format!("lane {} has two notes at beat {}", lane, beat)));
+                }
+                *cell = symbol;
+            }
+            Ok(grid.iter().map(|row| row.iter().collect::<String>()).collect::<Vec<_>>().join("\n"))
+        })
+        .collect::<Result<Vec<_>, ChartError>>()?;
+
+    Ok(measures.join("\n,\n"))
+}
+
+// Beat as a whole number of 192nd notes, simfiles can't place anything between them
+fn to_ticks(beat: Beat) -> Result<i64, ChartError> {
+    let ticks = beat * Beat::whole(TICKS_PER_BEAT);
+    if ticks.denominator() != 1 {
+        return Err(ChartError::Parse(format!("beat {} is off the 192nd note grid simfiles use", beat)));
+    }
+    Ok(ticks.numerator())
+}
@@ -0,0 +1,103 @@
+// Shared conversion for formats that place everything in milliseconds (osu!, Quaver)
+// Their timing points are rebuilt as beats on our tempo map
+
+use super::{BpmChange, Chart, ChartError, ChartMetadata, ChartNote, NoteKind, ScrollVelocity, TempoMap, TimeSignature};
+use crate::types::Beat;
+
+const SNAP_TOLERANCE_MS: f32 = 1.0; // Timestamps are stored in whole milliseconds
+
+pub(crate) struct TimedBpm {
+    pub time: f32, // Milliseconds
+    pub bpm: f32,
+    pub meter: u32, // Beats per measure, every BPM point starts a new measure
+}
+
+pub(crate) struct TimedScrollVelocity {
+    pub time: f32,
+    pub multiplier: f32,
+}
+
+pub(crate) struct TimedNote {
+    pub lane: usize,
+    pub time: f32,
+    pub end_time: Option<f32>,
+}
+
+pub(crate) fn build_timed_chart(
+    metadata: ChartMetadata,
+    audio: Option<String>,
+    mut bpms: Vec<TimedBpm>,
+    mut scroll_velocities: Vec<TimedScrollVelocity>,
+    notes: Vec<TimedNote>,
+) -> Result<Chart, ChartError> {
+    bpms.sort_by(|a, b| a.time.total_cmp(&b.time));
+    scroll_velocities.sort_by(|a, b| a.time.total_cmp(&b.time));
+    let Some(first) = bpms.first() else {
+        return Err(ChartError::Parse("chart has no BPM timing points".to_string()));
+    };
+    if let Some(point) = bpms.iter().find(|point| !point.bpm.is_finite() || point.bpm <= 0.0) {
+        return Err(ChartError::Parse(format!("invalid BPM {} at {}ms", point.bpm, point.time)));
+    }
+
+    // Beat 0 sits on the first timing point, pulled back by whole beats if notes come before it
+    let beat_length = |bpm: f32| 60_000.0 / bpm;
+    let earliest_note = notes.iter().map(|note| note.time).fold(first.time, f32::min);
+    let lead_beats = ((first.time - earliest_note) / beat_length(first.bpm)).ceil();
+    let offset_ms = first.time - lead_beats * beat_length(first.bpm);
+
+    let mut bpm_changes = Vec::new();
+    let mut time_signatures = Vec::new();
+    if first.meter != TimeSignature::COMMON.numerator || lead_beats > 0.0 {
+        time_signatures.push(TimeSignature { beat: Beat::from_f32(lead_beats), numerator: first.meter, denominator: 4 });
+    }
+    let mut beat = lead_beats;
+    for pair in bpms.windows(2) {
+        beat += (pair[1].time - pair[0].time) / beat_length(pair[0].bpm);
+        bpm_changes.push(BpmChange { beat: Beat::from_f32(beat), bpm: pair[1].bpm });
+        if pair[1].meter != pair[0].meter {
+            time_signatures.push(TimeSignature { beat: Beat::from_f32(beat), numerator: pair[1].meter, denominator: 4 });
+        }
+    }
+
+    let tempo_map = TempoMap::new(offset_ms / 1000.0, first.bpm, &bpm_changes, &[], &[], &[]);
+    let beat_at = |time_ms: f32| {
+        let beat = tempo_map.seconds_to_beat(time_ms / 1000.0).max(0.0);
+        Beat::snap(beat, (SNAP_TOLERANCE_MS * tempo_map.bpm_at(beat) / 60_000.0) as f64)
+    };
+
+    // Only keep points that actually change the speed
+    let mut velocities: Vec<ScrollVelocity> = Vec::new();
+    for point in &scroll_velocities {
+        let current = velocities.last().map_or(1.0, |velocity| velocity.multiplier);
+        if point.multiplier != current {
+            velocities.push(ScrollVelocity { beat: beat_at(point.time), multiplier: point.multiplier });
+        }
+    }
+
+    let notes = notes
+        .iter()
+        .map(|note| ChartNote {
+            beat: beat_at(note.time),
+            lane: note.lane,
+            kind: NoteKind::Tap,
+            end_beat: note.end_time.map(beat_at),
+            keysound: None,
+        })
+        .collect();
+
+    Chart {
+        metadata,
+        audio,
+        offset: offset_ms / 1000.0,
+        bpm: first.bpm,
+        bpm_changes,
+        stops: Vec::new(),
+        delays: Vec::new(),
+        time_signatures,
+        scroll_velocities: velocities,
+        keysounds: Vec::new(),
+        background_sounds: Vec::new(),
+        notes,
+    }
+    .validate()
+}
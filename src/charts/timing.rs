@@ -0,0 +1,236 @@
+// Beat <-> time conversion for charts with BPM changes, stops, delays and time signatures
+
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+use crate::types::Beat;
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq)]
+pub struct BpmChange {
+    pub beat: Beat,
+    pub bpm: f32,
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq)]
+pub struct Stop {
+    pub beat: Beat,
+    pub duration: f32, // Seconds the scroll freezes for, notes on the stop's beat are hit before it
+}
+
+// Same freeze as a stop, but notes on the delay's beat are hit after it (StepMania #DELAYS)
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq)]
+pub struct Delay {
+    pub beat: Beat,
+    pub duration: f32,
+}
+
+// Starts a new measure at `beat`; the denominator picks the note value that counts as one metronome click
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq)]
+pub struct TimeSignature {
+    pub beat: Beat,
+    pub numerator: u32,
+    pub denominator: u32,
+}
+
+impl TimeSignature {
+    pub const COMMON: TimeSignature = TimeSignature { beat: Beat::ZERO, numerator: 4, denominator: 4 };
+    
+    // Length of one counted beat, in quarter notes (an eighth in 6/8)
+    pub fn beat_length(&self) -> Beat {
+        Beat::new(4, self.denominator as i64)
+    }
+    
+    pub fn measure_length(&self) -> Beat {
+        Beat::new(4 * self.numerator as i64, self.denominator as i64)
+    }
+}
+
+// Visual scroll speed change (osu! inherited timing points, Quaver slider velocities)
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq)]
+pub struct ScrollVelocity {
+    pub beat: Beat,
+    pub multiplier: f32,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+struct TempoSegment {
+    beat: f64,
+    bpm: f64,
+    seconds: f64, // Time at the start of the segment, ignoring pauses and offset
+}
+
+// A stop or delay, both freeze the beat for a while
+#[derive(Clone, Copy, Debug, PartialEq)]
+struct Pause {
+    beat: f64,
+    seconds: f64,
+    before_beat: bool, // Delays pause before notes on their beat, stops after
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+struct MeasureSegment {
+    signature: TimeSignature,
+    measure: i64, // Index of the measure starting at `signature.beat`
+}
+
+// Timing of the chart being played
+// Works in f64 internally so long charts don't pick up rounding drift
+#[derive(Resource, Clone, Debug, PartialEq)]
+pub struct TempoMap {
+    offset: f64,
+    segments: Vec<TempoSegment>,
+    pauses: Vec<Pause>,
+    measures: Vec<MeasureSegment>,
+}
+
+impl TempoMap {
+    // `bpm` applies from beat 0 until the first change and 4/4 is assumed until the first signature;
+    // changes must be sorted by beat
+    pub fn new(
+        offset: f32,
+        bpm: f32,
+        bpm_changes: &[BpmChange],
+        stops: &[Stop],
+        delays: &[Delay],
+        time_signatures: &[TimeSignature],
+    ) -> Self {
+        let mut segments = vec![TempoSegment { beat: 0.0, bpm: bpm as f64, seconds: 0.0 }];
+        for change in bpm_changes {
+            let last = segments[segments.len() - 1];
+            let beat = change.beat.to_f64();
+            if beat <= last.beat {
+                // A change on the same beat (or before beat 0) just replaces the tempo
+                segments.last_mut().unwrap().bpm = change.bpm as f64;
+                continue;
+            }
+            segments.push(TempoSegment {
+                beat,
+                bpm: change.bpm as f64,
+                seconds: last.seconds + (beat - last.beat) * 60.0 / last.bpm,
+            });
+        }
+
+        let mut pauses: Vec<Pause> = stops
+            .iter()
+            .map(|stop| Pause { beat: stop.beat.to_f64(), seconds: stop.duration as f64, before_beat: false })
+            .chain(delays.iter().map(|delay| Pause { beat: delay.beat.to_f64(), seconds: delay.duration as f64, before_beat: true }))
+            .collect();
+        pauses.sort_by(|a, b| a.beat.total_cmp(&b.beat).then(b.before_beat.cmp(&a.before_beat)));
+
+        let mut measures = vec![MeasureSegment { signature: TimeSignature::COMMON, measure: 0 }];
+        for signature in time_signatures {
+            let last = measures[measures.len() - 1];
+            if signature.beat <= last.signature.beat {
+                measures.last_mut().unwrap().signature = TimeSignature { beat: last.signature.beat, ..*signature };
+                continue;
+            }
+            // A signature landing mid-measure cuts that measure short
+            let elapsed = (signature.beat - last.signature.beat) / last.signature.measure_length();
+            measures.push(MeasureSegment { signature: *signature, measure: last.measure + elapsed.ceil() });
+        }
+
+        Self { offset: offset as f64, segments, pauses, measures }
+    }
+
+    pub fn constant(bpm: f32) -> Self {
+        Self::new(0.0, bpm, &[], &[], &[], &[])
+    }
+
+    pub fn offset(&self) -> f32 {
+        self.offset as f32
+    }
+
+    pub fn bpm_at(&self, beat: impl Into<f64>) -> f32 {
+        self.segment_at(beat.into()).bpm as f32
+    }
+
+    // Time in seconds (relative to the song start) at which a beat lands
+    pub fn beat_to_seconds(&self, beat: impl Into<f64>) -> f32 {
+        let beat = beat.into();
+        let paused: f64 = self
+            .pauses
+            .iter()
+            .filter(|pause| pause.beat < beat || (pause.before_beat && pause.beat == beat))
+            .map(|pause| pause.seconds)
+            .sum();
+        (self.offset + self.unpaused_seconds(beat) + paused) as f32
+    }
+
+    // Beat position at a time in seconds (relative to the song start), holding still during stops and delays
+    pub fn seconds_to_beat(&self, seconds: f32) -> f32 {
+        let mut unpaused = seconds as f64 - self.offset;
+        for pause in &self.pauses {
+            let pause_start = self.unpaused_seconds(pause.beat);
+            if unpaused <= pause_start {
+                break;
+            }
+            if unpaused < pause_start + pause.seconds {
+                return pause.beat as f32;
+            }
+            unpaused -= pause.seconds;
+        }
+
+        let segment = self
+            .segments
+            .iter()
+            .rev()
+            .find(|segment| segment.seconds <= unpaused)
+            .unwrap_or(&self.segments[0]);
+        (segment.beat + (unpaused - segment.seconds) * segment.bpm / 60.0) as f32
+    }
+
+    pub fn time_signature_at(&self, beat: Beat) -> TimeSignature {
+        self.measure_segment_at(beat).signature
+    }
+
+    // Measure a beat falls in (0 based, negative during the lead-in) and how far into it the beat is
+    pub fn measure_position(&self, beat: Beat) -> (i64, Beat) {
+        let segment = self.measure_segment_at(beat);
+        let measure_length = segment.signature.measure_length();
+        let measures = ((beat - segment.signature.beat) / measure_length).floor();
+        let measure_start = segment.signature.beat + measure_length * Beat::whole(measures);
+        (segment.measure + measures, beat - measure_start)
+    }
+
+    // First metronome click strictly after `beat`, clicks fall on every counted beat of the time signature
+    pub fn next_click(&self, beat: Beat) -> Beat {
+        let segment = self.measure_segment_at(beat);
+        let click_length = segment.signature.beat_length();
+        let clicks = ((beat - segment.signature.beat) / click_length).floor() + 1;
+        let click = segment.signature.beat + click_length * Beat::whole(clicks);
+
+        // A new signature restarts the count on its own beat
+        self.measures
+            .iter()
+            .map(|next| next.signature.beat)
+            .find(|&start| start > beat && start < click)
+            .unwrap_or(click)
+    }
+
+    fn segment_at(&self, beat: f64) -> &TempoSegment {
+        self.segments
+            .iter()
+            .rev()
+            .find(|segment| segment.beat <= beat)
+            .unwrap_or(&self.segments[0])
+    }
+
+    fn measure_segment_at(&self, beat: Beat) -> &MeasureSegment {
+        self.measures
+            .iter()
+            .rev()
+            .find(|segment| segment.signature.beat <= beat)
+            .unwrap_or(&self.measures[0])
+    }
+
+    fn unpaused_seconds(&self, beat: f64) -> f64 {
+        let segment = self.segment_at(beat);
+        segment.seconds + (beat - segment.beat) * 60.0 / segment.bpm
+    }
+}
+
+// Placeholder until a chart is loaded: 120 BPM in 4/4
+impl Default for TempoMap {
+    fn default() -> Self {
+        Self::constant(120.0)
+    }
+}
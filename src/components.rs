@@ -1,10 +1,32 @@
 use bevy::prelude::*;
+use crate::charts::NoteKind;
 
 #[derive(Component)]
 pub struct Note {
     pub lane: usize,
+    pub kind: NoteKind,
+    pub keysound: Option<usize>, // Index into `KeysoundBank::samples`
+    pub hit_time: f32, // `SongClock` time at which the note reaches the target
+    pub end_time: Option<f32>, // `SongClock` time a hold note's tail reaches the target
 }
 
+// Hold or roll whose head was hit and that is still being played
+#[derive(Component)]
+pub struct Holding {
+    pub last_press: f32, // Rolls die once the lane goes too long without a tap
+}
+
+#[derive(Component)]
+pub struct HoldDropped {
+    pub time: f32, // When it was let go (or its head missed), the body left after that falls away
+}
+
+#[derive(Component)]
+pub struct Missed; // Judged note that passed its late window unhit, left to scroll off
+
+#[derive(Component)]
+pub struct HoldBody; // Stretched sprite between a hold note's head and tail, a child of the head
+
 #[derive(Component)]
 pub struct NoteTargetTriggered; // Marker to prevent duplicate target sounds
 
@@ -45,6 +67,42 @@ pub struct ScoreUI;
 #[derive(Component)]
 pub struct StreakUI;
 
+#[derive(Component)]
+pub struct LifeBarFill; // Part of the life bar that shows the health left
+
+#[derive(Component)]
+pub struct FailedUI; // "FAILED" screen shown once the life gauge runs out
+
+#[derive(Component)]
+pub struct JudgementCountsUI; // Per-judgement counters, max combo and accuracy under the score
+
+#[derive(Component)]
+pub struct Playfield; // Lanes, targets and HUD of the chart being played, taken down when it's over
+
+#[derive(Component)]
+pub struct TitleUI;
+
+#[derive(Component)]
+pub struct SongSelectUI;
+
+#[derive(Component)]
+pub struct SongListText;
+
+#[derive(Component)]
+pub struct SongDetailsText; // Title, artist, BPM, length and difficulties of the picked song
+
+#[derive(Component)]
+pub struct LoadingUI;
+
+#[derive(Component)]
+pub struct ResultsUI;
+
+#[derive(Component)]
+pub struct PauseMenuUI; // Dimmed overlay over the paused playfield
+
+#[derive(Component)]
+pub struct PauseMenuText; // Menu options, or the resume countdown once Resume is picked
+
 #[derive(Component)]
 pub struct MetronomeFlash;
 
@@ -1,15 +1,3 @@
-// Musical timing constants (60 BPM for testing - slower tempo)
-pub const BPM: f32 = 60.0; // Slowed down from 120.0 for testing
-pub const BEAT_INTERVAL: f32 = 60.0 / BPM; // 1.0 seconds per beat at 60 BPM
-
-// Note durations in seconds
-pub const WHOLE_NOTE: f32 = BEAT_INTERVAL * 4.0; // 2.0 seconds
-pub const HALF_NOTE: f32 = BEAT_INTERVAL * 2.0;  // 1.0 seconds
-pub const QUARTER_NOTE: f32 = BEAT_INTERVAL;     // 0.5 seconds
-pub const EIGHTH_NOTE: f32 = BEAT_INTERVAL / 2.0; // 0.25 seconds
-pub const SIXTEENTH_NOTE: f32 = BEAT_INTERVAL / 4.0; // 0.125 seconds
-pub const TRIPLET_NOTE: f32 = BEAT_INTERVAL * 2.0 / 3.0; // 0.333 seconds (triplet eighth)
-
 // Game constants
 pub const WINDOW_WIDTH: f32 = 800.0;
 pub const WINDOW_HEIGHT: f32 = 600.0;
@@ -18,7 +6,27 @@ pub const LANES: [f32; 4] = [-150.0, -50.0, 50.0, 150.0];
 pub const NOTE_SIZE: f32 = 40.0;
 pub const TARGET_Y: f32 = -200.0;
 pub const NOTE_SPEED: f32 = 150.0; // Faster than 50, but not as fast as original 200
-pub const HIT_TOLERANCE: f32 = 0.15; // 150ms tolerance
+pub const HOLD_BODY_WIDTH: f32 = NOTE_SIZE * 0.6;
+pub const MINE_SIZE: f32 = NOTE_SIZE * 0.6;
+pub const LIFT_HEIGHT: f32 = NOTE_SIZE * 0.35; // Lifts are drawn as flat bars
+pub const MINE_PENALTY: u32 = 50; // Score lost for every mine that goes off
+
+// Scoring systems
+pub const MILLION_SCORE_MAX: u64 = 1_000_000;
+pub const EX_SCORE_BEST: u64 = 3; // EX points for the best tier
+pub const ARCADE_COMBO_STEP: u32 = 10; // Combo needed for each step of the arcade multiplier
+pub const ARCADE_MAX_MULTIPLIER: u32 = 4;
+
+// Health gained or lost (out of 1.0) with the normal drain model
+pub const HEALTH_HIT_GAIN: f32 = 0.01;
+pub const HEALTH_SLOPPY_PENALTY: f32 = 0.02; // Hits on tiers that break the combo
+pub const MISS_HEALTH_PENALTY: f32 = 0.08;
+pub const MINE_HEALTH_PENALTY: f32 = 0.05;
+
+// Life bar, to the right of the lanes and growing up from the target line
+pub const LIFE_BAR_X: f32 = 230.0;
+pub const LIFE_BAR_WIDTH: f32 = 16.0;
+pub const LIFE_BAR_HEIGHT: f32 = 400.0;
 
 // Travel time calculation - recalculated for exact timing
 pub const SPAWN_Y: f32 = WINDOW_HEIGHT/2.0 + 100.0;
@@ -26,14 +34,38 @@ pub const TRAVEL_DISTANCE: f32 = SPAWN_Y - TARGET_Y; // Distance from spawn to t
 // Calculate exact travel time: 400 - (-200) = 600 pixels, 600/150 = 4.0 seconds exactly
 pub const TRAVEL_TIME: f32 = 4.0; // Back to calculated value for precision
 
-// Scoring constants
-pub const PERFECT_SCORE: i32 = 100;
-pub const GOOD_SCORE: i32 = 50;
-pub const OKAY_SCORE: i32 = 20;
+// Settings file read at startup, relative to the working directory (defaults are used if it's missing)
+pub const SETTINGS_PATH: &str = "assets/settings.yaml";
+
+// Song library, scanned at startup: every folder in the songs folder with a song file and charts is a song
+pub const ASSETS_DIR: &str = "assets";
+pub const SONGS_DIR: &str = "songs"; // Relative to the assets folder
+pub const DEMO_SONG_DIR: &str = "charts"; // The bundled demo chart, always in the library
+pub const SONG_LIST_ROWS: usize = 10; // Songs shown at once on the song select, the list scrolls past that
+
+// Lead-in before beat 0 whenever a chart (re)starts
+pub const SONG_LEAD_IN: f32 = 1.0;
+
+// Pause between the last note (or failing) and the results screen
+pub const RESULTS_DELAY: f32 = 2.0;
+
+// Results screen graphs: hit offsets over song time, and how many hits landed at each offset
+pub const RESULTS_GRAPH_WIDTH: f32 = 440.0;
+pub const SCATTER_HEIGHT: f32 = 220.0;
+pub const HISTOGRAM_HEIGHT: f32 = 140.0;
+pub const HISTOGRAM_BINS: usize = 31;
+
+// Countdown after picking Resume on the pause menu, before the song picks back up
+pub const RESUME_COUNTDOWN: f32 = 3.0;
+
+// Cue samples, relative to the assets folder
+pub const METRONOME_CLICK_PATH: &str = "sounds/click.wav";
+pub const METRONOME_ACCENT_PATH: &str = "sounds/click_accent.wav";
+pub const TARGET_CUE_PATH: &str = "sounds/pop.wav";
 
-pub const PERFECT_TOLERANCE: f32 = 0.05; // 50ms for perfect
-pub const GOOD_TOLERANCE: f32 = 0.10;    // 100ms for good
-pub const OKAY_TOLERANCE: f32 = HIT_TOLERANCE; // 150ms for okay
+pub const METRONOME_VOLUME: f64 = 0.6;
+pub const METRONOME_ACCENT_VOLUME: f64 = 1.5; // Downbeats are this much louder than the other clicks
+pub const TARGET_CUE_VOLUME: f64 = 0.4;
 
 // UI constants
 pub const SCORE_FONT_SIZE: f32 = 30.0;
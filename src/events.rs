@@ -0,0 +1,9 @@
+use bevy::prelude::*;
+
+// A lane key going down or coming back up, stamped with the song time it happened at
+#[derive(Event, Clone, Copy, Debug)]
+pub struct LaneInput {
+    pub lane: usize,
+    pub time: f32, // `SongClock` time of the press or release
+    pub pressed: bool, // False when the key was let go
+}
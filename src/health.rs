@@ -0,0 +1,110 @@
+// Life gauge: good judgements fill it, misses and mines drain it, and the run fails once it's empty
+
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+use crate::constants::*;
+
+// How judgements move the life gauge, picked in the settings file
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq)]
+#[serde(tag = "model", rename_all = "snake_case")]
+pub enum DrainModel {
+    #[default]
+    Normal, // Good hits refill the gauge, sloppy hits, misses and mines drain it
+    SuddenDeath, // Anything that breaks the combo (or a mine) fails the run
+    Battery { lives: u32 }, // Misses, drops and mines each cost a life, nothing gives them back
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq)]
+#[serde(default)]
+pub struct LifeSettings {
+    pub drain: DrainModel,
+    pub no_fail: bool, // Keep playing with an empty gauge
+}
+
+#[derive(Resource, Clone, Debug)]
+pub struct Health {
+    pub value: f32, // 0 (empty) to 1 (full)
+    pub lives: u32, // Lives left with the battery model
+    pub drain: DrainModel,
+    pub no_fail: bool,
+    pub failed: bool, // Set once the gauge empties without no-fail, the run is over
+}
+
+impl Default for Health {
+    fn default() -> Self {
+        Health::new(&LifeSettings::default())
+    }
+}
+
+impl Health {
+    pub fn new(settings: &LifeSettings) -> Self {
+        let mut health = Self { value: 1.0, lives: 0, drain: settings.drain, no_fail: settings.no_fail, failed: false };
+        health.reset();
+        health
+    }
+    
+    // Full gauge (or every life back) for a fresh run
+    pub fn reset(&mut self) {
+        self.value = 1.0;
+        self.lives = match self.drain {
+            DrainModel::Battery { lives } => lives.max(1),
+            _ => 0,
+        };
+        self.failed = false;
+    }
+    
+    // A judged press or release, `breaks_combo` for the sloppy tiers
+    pub fn hit(&mut self, breaks_combo: bool) {
+        if self.failed {
+            return;
+        }
+        match (self.drain, breaks_combo) {
+            (DrainModel::Normal, false) => self.change(HEALTH_HIT_GAIN),
+            (DrainModel::Normal, true) => self.change(-HEALTH_SLOPPY_PENALTY),
+            (DrainModel::SuddenDeath, true) => self.change(-1.0),
+            _ => {}
+        }
+    }
+    
+    // A missed note or a hold let go too early
+    pub fn miss(&mut self) {
+        match self.drain {
+            DrainModel::Normal => self.change(-MISS_HEALTH_PENALTY),
+            DrainModel::SuddenDeath => self.change(-1.0),
+            DrainModel::Battery { .. } => self.lose_life(),
+        }
+    }
+    
+    pub fn mine(&mut self) {
+        match self.drain {
+            DrainModel::Normal => self.change(-MINE_HEALTH_PENALTY),
+            DrainModel::SuddenDeath => self.change(-1.0),
+            DrainModel::Battery { .. } => self.lose_life(),
+        }
+    }
+    
+    fn lose_life(&mut self) {
+        let DrainModel::Battery { lives } = self.drain else { return };
+        if self.failed {
+            return;
+        }
+        self.lives = self.lives.saturating_sub(1);
+        self.value = self.lives as f32 / lives.max(1) as f32;
+        self.check_failed();
+    }
+    
+    fn change(&mut self, amount: f32) {
+        // Nothing comes back once the run has failed
+        if self.failed {
+            return;
+        }
+        self.value = (self.value + amount).clamp(0.0, 1.0);
+        self.check_failed();
+    }
+    
+    fn check_failed(&mut self) {
+        if self.value <= 0.0 && !self.no_fail {
+            self.failed = true;
+        }
+    }
+}
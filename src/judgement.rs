@@ -0,0 +1,198 @@
+// Judgement windows: how far a press can be from a note's hit time and what each tier is worth
+
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct JudgementTier {
+    pub name: String,
+    pub window_ms: f32, // Largest early/late offset that still earns this tier
+    pub score: u32,
+    #[serde(default)]
+    pub breaks_combo: bool,
+    #[serde(default = "default_tier_color")]
+    pub color: [f32; 3], // sRGB of the popup text
+}
+
+fn default_tier_color() -> [f32; 3] {
+    [0.8, 0.8, 0.8]
+}
+
+impl JudgementTier {
+    fn new(name: &str, window_ms: f32, score: u32, breaks_combo: bool, color: [f32; 3]) -> Self {
+        Self { name: name.to_string(), window_ms, score, breaks_combo, color }
+    }
+
+    pub fn color(&self) -> Color {
+        Color::srgb(self.color[0], self.color[1], self.color[2])
+    }
+}
+
+const YELLOW: [f32; 3] = [1.0, 1.0, 0.0];
+const WHITE: [f32; 3] = [1.0, 1.0, 1.0];
+const GREEN: [f32; 3] = [0.0, 1.0, 0.0];
+const BLUE: [f32; 3] = [0.0, 0.8, 1.0];
+const PURPLE: [f32; 3] = [0.8, 0.4, 1.0];
+const GRAY: [f32; 3] = [0.8, 0.8, 0.8];
+
+// StepMania's roll timing window
+const DEFAULT_ROLL_WINDOW_MS: f32 = 500.0;
+
+// Etterna's J4 windows in ms, every other judge scales them
+const ETTERNA_J4_WINDOWS: [f32; 5] = [22.5, 45.0, 90.0, 135.0, 180.0];
+const ETTERNA_JUDGE_SCALES: [f32; 9] = [1.50, 1.33, 1.16, 1.00, 0.84, 0.66, 0.50, 0.33, 0.20];
+
+// Active windows, tightest tier first
+#[derive(Resource, Clone, Debug, PartialEq)]
+pub struct JudgementWindows {
+    pub name: String,
+    pub tiers: Vec<JudgementTier>,
+    pub release_window_ms: f32, // How early a hold can be let go of and still count as held to its tail
+    pub roll_window_ms: f32, // Longest a roll can go without a tap before it's dropped
+}
+
+impl JudgementWindows {
+    // Tiers can come in any order, holds may be released as early as the widest window by default
+    pub fn new(name: impl Into<String>, mut tiers: Vec<JudgementTier>) -> Self {
+        tiers.sort_by(|a, b| a.window_ms.total_cmp(&b.window_ms));
+        let release_window_ms = tiers.iter().map(|tier| tier.window_ms).fold(0.0, f32::max);
+        Self { name: name.into(), tiers, release_window_ms, roll_window_ms: DEFAULT_ROLL_WINDOW_MS }
+    }
+    
+    // Tier earned by a press `offset` seconds from the note (negative is early), None if it's outside every window
+    pub fn judge(&self, offset: f32) -> Option<usize> {
+        let offset_ms = offset.abs() * 1000.0;
+        self.tiers.iter().position(|tier| offset_ms <= tier.window_ms)
+    }
+
+    // Tier for letting go of a hold `offset` seconds from its tail, None if that's too early and the hold is dropped
+    pub fn judge_release(&self, offset: f32) -> Option<usize> {
+        if offset * 1000.0 < -self.release_window_ms {
+            return None;
+        }
+        // A release window wider than the tiers still earns the loosest one
+        self.judge(offset).or(self.tiers.len().checked_sub(1))
+    }
+
+    // Score of the best tier, what a judgement counts out of for accuracy
+    pub fn max_score(&self) -> u32 {
+        self.tiers.iter().map(|tier| tier.score).max().unwrap_or(0)
+    }
+
+    // Widest window in seconds, presses further out than this don't touch the note
+    pub fn widest(&self) -> f32 {
+        self.tiers.iter().map(|tier| tier.window_ms).fold(0.0, f32::max) / 1000.0
+    }
+
+    pub fn lenient() -> Self {
+        Self::new(
+            "Lenient",
+            vec![
+                JudgementTier::new("PERFECT!", 75.0, 100, false, YELLOW),
+                JudgementTier::new("GOOD", 150.0, 50, false, GREEN),
+                JudgementTier::new("OKAY", 225.0, 20, true, GRAY),
+            ],
+        )
+    }
+
+    pub fn standard() -> Self {
+        Self::new(
+            "Standard",
+            vec![
+                JudgementTier::new("PERFECT!", 50.0, 100, false, YELLOW),
+                JudgementTier::new("GOOD", 100.0, 50, false, GREEN),
+                JudgementTier::new("OKAY", 150.0, 20, true, GRAY),
+            ],
+        )
+    }
+
+    pub fn strict() -> Self {
+        Self::new(
+            "Strict",
+            vec![
+                JudgementTier::new("PERFECT!", 30.0, 100, false, YELLOW),
+                JudgementTier::new("GOOD", 60.0, 50, false, GREEN),
+                JudgementTier::new("OKAY", 100.0, 20, true, GRAY),
+            ],
+        )
+    }
+
+    // osu!mania (score v1) windows, which tighten by 3ms per OD point; only misses break combo there
+    pub fn osu_mania(od: f32) -> Self {
+        let od = od.clamp(0.0, 10.0);
+        Self::new(
+            format!("osu!mania OD{}", od),
+            vec![
+                JudgementTier::new("MAX", 16.0, 320, false, WHITE),
+                JudgementTier::new("300", 64.0 - 3.0 * od, 300, false, YELLOW),
+                JudgementTier::new("200", 97.0 - 3.0 * od, 200, false, GREEN),
+                JudgementTier::new("100", 127.0 - 3.0 * od, 100, false, BLUE),
+                JudgementTier::new("50", 151.0 - 3.0 * od, 50, false, GRAY),
+            ],
+        )
+    }
+
+    // Etterna judge 1-9 (J4 is the usual one), Goods and Bads break combo
+    pub fn etterna(judge: u32) -> Self {
+        let judge = judge.clamp(1, ETTERNA_JUDGE_SCALES.len() as u32);
+        let scale = ETTERNA_JUDGE_SCALES[judge as usize - 1];
+        let window = |index: usize| ETTERNA_J4_WINDOWS[index] * scale;
+        Self::new(
+            format!("Etterna J{}", judge),
+            vec![
+                JudgementTier::new("MARVELOUS", window(0), 300, false, WHITE),
+                JudgementTier::new("PERFECT", window(1), 300, false, YELLOW),
+                JudgementTier::new("GREAT", window(2), 200, false, GREEN),
+                JudgementTier::new("GOOD", window(3), 100, true, BLUE),
+                JudgementTier::new("BAD", window(4), 50, true, PURPLE),
+            ],
+        )
+    }
+}
+
+impl Default for JudgementWindows {
+    fn default() -> Self {
+        JudgementWindows::standard()
+    }
+}
+
+// Window preset picked in the settings file
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq)]
+#[serde(tag = "preset", rename_all = "snake_case")]
+pub enum JudgementPreset {
+    Lenient,
+    #[default]
+    Standard,
+    Strict,
+    OsuMania { od: f32 },
+    Etterna { judge: u32 },
+    Custom { name: String, tiers: Vec<JudgementTier> },
+}
+
+impl JudgementPreset {
+    // Custom tiers have to be listed tightest first, with positive windows that keep getting wider
+    pub fn validate(&self) -> Result<(), String> {
+        let JudgementPreset::Custom { tiers, .. } = self else { return Ok(()) };
+        if tiers.is_empty() {
+            return Err("a custom preset needs at least one tier".to_string());
+        }
+        if let Some(tier) = tiers.iter().find(|tier| !tier.window_ms.is_finite() || tier.window_ms <= 0.0) {
+            return Err(format!("tier {} has an invalid window of {}ms", tier.name, tier.window_ms));
+        }
+        if let Some(pair) = tiers.windows(2).find(|pair| pair[1].window_ms <= pair[0].window_ms) {
+            return Err(format!("tier {} ({}ms) has to be wider than {} ({}ms)", pair[1].name, pair[1].window_ms, pair[0].name, pair[0].window_ms));
+        }
+        Ok(())
+    }
+
+    pub fn windows(&self) -> JudgementWindows {
+        match self {
+            JudgementPreset::Lenient => JudgementWindows::lenient(),
+            JudgementPreset::Standard => JudgementWindows::standard(),
+            JudgementPreset::Strict => JudgementWindows::strict(),
+            JudgementPreset::OsuMania { od } => JudgementWindows::osu_mania(*od),
+            JudgementPreset::Etterna { judge } => JudgementWindows::etterna(*judge),
+            JudgementPreset::Custom { name, tiers } => JudgementWindows::new(name.clone(), tiers.clone()),
+        }
+    }
+}
@@ -1,15 +1,36 @@
 // Library crate for Rusty Rhythm
 // This allows us to organize code into modules while keeping main.rs clean
 
+// Bevy system signatures routinely trip these
+#![allow(clippy::type_complexity, clippy::too_many_arguments)]
+
+pub mod charts;
 pub mod components;
 pub mod resources;
+pub mod scoring;
+pub mod screens;
+pub mod states;
 pub mod systems;
 pub mod constants;
+pub mod events;
+pub mod health;
+pub mod judgement;
+pub mod library;
+pub mod settings;
 pub mod types;
 
 // Re-export commonly used items for convenience
+pub use charts::*;
 pub use components::*;
 pub use resources::*;
+pub use scoring::*;
+pub use screens::*;
+pub use states::*;
 pub use systems::*;
 pub use constants::*;
+pub use events::*;
+pub use health::*;
+pub use judgement::*;
+pub use library::*;
+pub use settings::*;
 pub use types::*;
\ No newline at end of file
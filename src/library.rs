@@ -0,0 +1,145 @@
+// Song library: the song folders under assets/songs, scanned at startup for the song select screen
+
+use bevy::asset::AssetPath;
+use bevy::prelude::*;
+use std::path::Path;
+use crate::charts::{difficulty_labels, file_extension, Chart, CHART_EXTENSIONS};
+use crate::types::Beat;
+
+pub const AUDIO_EXTENSIONS: &[&str] = &["ogg", "mp3", "wav", "flac"];
+
+// One playable chart of a song
+#[derive(Clone, Debug)]
+pub struct SongDifficulty {
+    pub name: String,
+    pub level: u32,
+    pub notes: usize, // Notes to play, mines and fakes don't count
+    pub path: String, // Asset path of the chart, labeled when its file holds several difficulties
+}
+
+#[derive(Clone, Debug)]
+pub struct Song {
+    pub folder: String, // Asset path of the song's folder
+    pub title: String,
+    pub artist: String,
+    pub bpm_range: (f32, f32),
+    pub length: f32, // Seconds until the last note of the longest difficulty is over
+    pub difficulties: Vec<SongDifficulty>, // Easiest first
+}
+
+impl Song {
+    // Every chart in an asset folder as one song, None if none of them load
+    pub fn load(assets_dir: &Path, folder: &str) -> Option<Song> {
+        let mut files: Vec<_> = std::fs::read_dir(assets_dir.join(folder))
+            .ok()?
+            .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+            .filter(|path| path.is_file() && CHART_EXTENSIONS.contains(&file_extension(path).as_str()))
+            .collect();
+        files.sort();
+
+        let mut charts = Vec::new();
+        let mut difficulties = Vec::new();
+        for file in files {
+            let file_charts = match Chart::load_all(&file) {
+                Ok(file_charts) => file_charts,
+                Err(error) => {
+                    warn!("Skipping chart {}: {}", file.display(), error);
+                    continue;
+                }
+            };
+            let file_name = file.file_name().and_then(|name| name.to_str()).unwrap_or_default();
+            let asset_path = AssetPath::from(Path::new(folder).join(file_name));
+            let labels = difficulty_labels(&file_charts);
+            for (label, chart) in labels.into_iter().zip(&file_charts) {
+                difficulties.push(SongDifficulty {
+                    name: if chart.metadata.difficulty.is_empty() { file_name.to_string() } else { chart.metadata.difficulty.clone() },
+                    level: chart.metadata.level,
+                    notes: chart.notes.iter().filter(|note| note.kind.is_judged()).count(),
+                    path: if file_charts.len() > 1 { asset_path.clone().with_label(label).to_string() } else { asset_path.to_string() },
+                });
+            }
+            charts.extend(file_charts);
+        }
+        if charts.is_empty() {
+            return None;
+        }
+        difficulties.sort_by_key(|difficulty| difficulty.level);
+
+        // Songs are named after their charts, or their folder when the charts don't say
+        let folder_name = folder.rsplit('/').next().unwrap_or(folder).to_string();
+        let title = charts.iter().map(|chart| &chart.metadata.title).find(|title| !title.is_empty()).cloned();
+        let artist = charts.iter().map(|chart| &chart.metadata.artist).find(|artist| !artist.is_empty()).cloned();
+        let bpms = charts.iter().flat_map(|chart| std::iter::once(chart.bpm).chain(chart.bpm_changes.iter().map(|change| change.bpm)));
+        let bpm_range = bpms.fold((f32::MAX, f32::MIN), |(low, high), bpm| (low.min(bpm), high.max(bpm)));
+        let length = charts
+            .iter()
+            .map(|chart| {
+                let last_beat = chart.notes.iter().map(|note| note.end_beat.unwrap_or(note.beat)).max();
+                chart.beat_to_seconds(last_beat.unwrap_or(Beat::ZERO))
+            })
+            .fold(0.0, f32::max);
+
+        Some(Song {
+            folder: folder.to_string(),
+            title: title.unwrap_or(folder_name),
+            artist: artist.unwrap_or_default(),
+            bpm_range,
+            length,
+            difficulties,
+        })
+    }
+
+    // "120" or "90-180"
+    pub fn bpm_label(&self) -> String {
+        let (low, high) = self.bpm_range;
+        if (high - low).abs() < 0.5 {
+            format!("{:.0}", low)
+        } else {
+            format!("{:.0}-{:.0}", low, high)
+        }
+    }
+
+    // "m:ss"
+    pub fn length_label(&self) -> String {
+        let seconds = self.length.max(0.0).round() as u32;
+        format!("{}:{:02}", seconds / 60, seconds % 60)
+    }
+}
+
+#[derive(Resource, Default)]
+pub struct SongLibrary {
+    pub songs: Vec<Song>,
+}
+
+impl SongLibrary {
+    // Every folder under `assets_dir/songs_dir` with a song file and at least one chart that loads, sorted by title
+    pub fn scan(assets_dir: impl AsRef<Path>, songs_dir: &str) -> SongLibrary {
+        let assets_dir = assets_dir.as_ref();
+        let entries = match std::fs::read_dir(assets_dir.join(songs_dir)) {
+            Ok(entries) => entries,
+            Err(error) => {
+                warn!("Couldn't read the songs folder {}: {}", assets_dir.join(songs_dir).display(), error);
+                return SongLibrary::default();
+            }
+        };
+
+        let mut songs: Vec<Song> = entries
+            .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+            .filter(|path| path.is_dir() && has_audio(path))
+            .filter_map(|path| {
+                let name = path.file_name()?.to_str()?;
+                Song::load(assets_dir, &format!("{}/{}", songs_dir, name))
+            })
+            .collect();
+        songs.sort_by_key(|song| song.title.to_lowercase());
+        SongLibrary { songs }
+    }
+}
+
+fn has_audio(dir: &Path) -> bool {
+    std::fs::read_dir(dir).is_ok_and(|entries| {
+        entries
+            .filter_map(|entry| entry.ok())
+            .any(|entry| AUDIO_EXTENSIONS.contains(&file_extension(&entry.path()).as_str()))
+    })
+}
@@ -4,48 +4,110 @@ use rusty_rhythm::*;
 use std::time::Duration;
 
 fn main() {
-    App::new()
-        .add_plugins(DefaultPlugins.set(WindowPlugin {
-            primary_window: Some(Window {
-                title: "Rusty Rhythm".into(),
-                resolution: (WINDOW_WIDTH, WINDOW_HEIGHT).into(),
+    // Charts are picked on the song select, `apply_chart_changes` sets all of this up when one loads
+    let note_spawn_timer = NoteSpawnTimer {
+        timer: Timer::new(Duration::from_secs_f32(0.1), TimerMode::Repeating), // Check every 0.1 seconds
+        song_start_time: SONG_LEAD_IN, // Song starts at 1 second
+        ..default()
+    };
+    
+    let mut app = App::new();
+    app.add_plugins(DefaultPlugins
+            .set(WindowPlugin {
+                primary_window: Some(Window {
+                    title: "Rusty Rhythm".into(),
+                    resolution: (WINDOW_WIDTH, WINDOW_HEIGHT).into(),
+                    ..default()
+                }),
                 ..default()
-            }),
-            ..default()
-        }))
-        .add_plugins(AudioPlugin)
-        .insert_resource(NoteSpawnTimer {
-            timer: Timer::new(Duration::from_secs_f32(0.1), TimerMode::Repeating), // Check every 0.1 seconds
-            current_pattern: create_demo_pattern(),
-            pattern_index: 0,
-            next_beat_time: 1.0, // First note hits target at 1 second (same as first metronome beat)
-            song_start_time: 1.0, // Song starts at 1 second
-        })
-        .insert_resource(GameScore {
-            score: 0,
-            streak: 0,
-        })
+            })
+            .set(AssetPlugin {
+                watch_for_changes_override: Some(true), // Hot reload charts while editing them
+                ..default()
+            }));
+    
+    // Read once logging is up, so problems with the settings file show in the log
+    let settings = Settings::load(SETTINGS_PATH);
+    let windows = settings.judgement_windows();
+    let health = Health::new(&settings.life);
+    info!("Judging with {} windows", windows.name);
+    
+    app.add_plugins(AudioPlugin)
+        .add_audio_channel::<MetronomeChannel>()
+        .add_audio_channel::<TargetCueChannel>()
+        .init_asset::<Chart>()
+        .init_asset_loader::<ChartLoader>()
+        .insert_resource(note_spawn_timer)
+        .init_resource::<TempoMap>()
+        .insert_resource(windows)
+        .insert_resource(settings)
+        .init_resource::<KeysoundBank>()
+        .init_resource::<HitLog>()
+        .init_resource::<LaneState>()
+        .init_resource::<SelectedChart>()
+        .init_resource::<GameScore>()
+        .add_event::<LaneInput>()
+        .insert_resource(SongClock { song_start_time: SONG_LEAD_IN, ..default() })
+        .insert_resource(health)
         .insert_resource(Metronome {
-            next_beat_time: 1.0, // First beat at 1 second
-            song_start_time: 1.0,
+            next_click: Beat::ZERO, // First click on beat 0, 1 second in
+            song_start_time: SONG_LEAD_IN,
             is_active: true,
-            audio_handle: None, // Will be loaded later if needed
+            audio_handle: None, // Loaded by load_cue_sounds
+            accent_handle: None,
+            volume: METRONOME_VOLUME,
+            accent_volume: METRONOME_ACCENT_VOLUME,
         })
-        .add_systems(Startup, setup)
+        .insert_resource(TargetCue {
+            is_active: true,
+            audio_handle: None,
+            volume: TARGET_CUE_VOLUME,
+        })
+        .init_state::<GameState>()
+        .add_computed_state::<InGame>()
+        .add_systems(Startup, (setup, load_cue_sounds, scan_song_library))
+        .add_systems(OnEnter(GameState::Title), spawn_title)
+        .add_systems(OnExit(GameState::Title), despawn_screen::<TitleUI>)
+        .add_systems(OnEnter(GameState::SongSelect), spawn_song_select)
+        .add_systems(OnExit(GameState::SongSelect), despawn_screen::<SongSelectUI>)
+        .add_systems(OnEnter(GameState::Loading), (load_chart, spawn_loading))
+        .add_systems(OnExit(GameState::Loading), despawn_screen::<LoadingUI>)
+        .add_systems(OnEnter(GameState::Paused), (pause_song, spawn_pause_menu))
+        .add_systems(OnExit(GameState::Paused), despawn_screen::<PauseMenuUI>)
+        .add_systems(OnTransition { exited: GameState::Paused, entered: GameState::Playing }, resume_song)
+        .add_systems(OnEnter(InGame), spawn_playfield)
+        .add_systems(OnExit(InGame), teardown_playfield)
+        .add_systems(OnEnter(GameState::Results), spawn_results)
+        .add_systems(OnExit(GameState::Results), despawn_screen::<ResultsUI>)
+        .add_systems(PreUpdate, update_song_clock.run_if(in_state(GameState::Playing)))
+        .add_systems(Update, (
+            title_input.run_if(in_state(GameState::Title)),
+            (song_select_input, update_song_select).chain().run_if(in_state(GameState::SongSelect)),
+            // Charts start while loading, and restart when they're edited on disk mid-song
+            apply_chart_changes.run_if(in_state(GameState::Loading).or_else(in_state(InGame))),
+            finish_loading.after(apply_chart_changes).run_if(in_state(GameState::Loading)),
+            results_input.run_if(in_state(GameState::Results)),
+            // Nothing of the song moves while paused, only the menu and its resume countdown
+            (pause_menu_input, resume_countdown, update_pause_menu).chain().run_if(in_state(GameState::Paused)),
+        ))
         .add_systems(Update, (
-            handle_input, 
-            spawn_notes, 
+            // Judge lane input as soon as it's read, then the holds and mines that depend on what's held,
+            // and only then call misses so a press on the last frame of a window still counts
+            (handle_input, judge_lane_input, handle_hold_notes, handle_mines, handle_missed_notes).chain(),
+            (spawn_notes, play_background_sounds, metronome_system).run_if(still_playing),
             move_notes, 
             note_target_detection,
             cleanup_notes, 
             animate_button_press, 
             cleanup_score_text, 
             update_ui, 
-            handle_missed_notes,
-            metronome_system,
             handle_metronome_flash,
-        ))
+            toggle_audio_cues,
+            apply_cue_volumes,
+            update_life_bar,
+            toggle_no_fail,
+            handle_failure,
+            check_chart_end,
+        ).run_if(in_state(GameState::Playing)))
         .run();
-    
-    println!("Rusty Rhythm initialized! 🎵🦀");
 }
\ No newline at end of file
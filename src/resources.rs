@@ -1,11 +1,15 @@
 use bevy::prelude::*;
 use std::time::Duration;
-use crate::types::NoteDuration;
+use crate::charts::{BackgroundSound, Chart, ChartNote, TempoMap};
+use crate::constants::LANES;
+use crate::judgement::JudgementWindows;
+use crate::scoring::{ScoringKind, ScoringSystem};
+use crate::types::{Beat, Grade};
 
 #[derive(Resource)]
 pub struct NoteSpawnTimer {
     pub timer: Timer,
-    pub current_pattern: Vec<(NoteDuration, usize)>, // (duration, lane) pairs
+    pub current_pattern: Vec<ChartNote>, // Notes in hit order
     pub pattern_index: usize,
     pub next_beat_time: f32, // When the next note should hit the target
     pub song_start_time: f32, // When the song started
@@ -23,25 +27,243 @@ impl Default for NoteSpawnTimer {
     }
 }
 
-#[derive(Resource)]
+impl NoteSpawnTimer {
+    // Replace the current pattern with a chart's notes and start it from the top
+    pub fn load_chart(&mut self, chart: &Chart, tempo_map: &TempoMap) {
+        self.current_pattern = chart.notes.clone();
+        self.pattern_index = 0;
+        self.next_beat_time = self.hit_time(tempo_map, self.current_pattern.first().map_or(Beat::ZERO, |note| note.beat));
+    }
+    
+    // Absolute time at which a beat of the current chart reaches the target
+    pub fn hit_time(&self, tempo_map: &TempoMap, beat: Beat) -> f32 {
+        self.song_start_time + tempo_map.beat_to_seconds(beat)
+    }
+}
+
+#[derive(Resource, Default)]
 pub struct GameScore {
-    pub score: u32,
+    pub scoring: Vec<Box<dyn ScoringSystem>>, // One per `ScoringKind::ALL`, all scoring the same run
     pub streak: u32,
+    pub max_combo: u32,
+    pub counts: Vec<u32>, // Judgements per `JudgementWindows` tier
+    pub misses: u32,
+    pub drops: u32, // Holds and rolls let go too early
+    pub mines_hit: u32,
+    pub total_notes: u32, // Notes to play in the chart, mines and fakes don't count
+    pub total_judgements: u32, // Presses and releases in the chart, the longest combo it allows since hold tails add to the streak too
+    pub points: u64, // Accuracy points earned so far, each judgement is worth up to the best tier's score
+    pub max_points: u64, // Accuracy points a flawless run would have by now
 }
 
-impl Default for GameScore {
-    fn default() -> Self {
+impl GameScore {
+    pub fn for_chart(chart: &Chart) -> Self {
+        let total_judgements = chart.notes.iter().map(|note| note.judgements()).sum();
         Self {
-            score: 0,
-            streak: 0,
+            scoring: ScoringKind::ALL.iter().map(|kind| kind.system(total_judgements)).collect(),
+            total_notes: chart.notes.iter().filter(|note| note.kind.is_judged()).count() as u32,
+            total_judgements,
+            ..default()
+        }
+    }
+    
+    // The run so far as scored by one of the scoring systems
+    pub fn system(&self, kind: ScoringKind) -> Option<&dyn ScoringSystem> {
+        let index = ScoringKind::ALL.iter().position(|other| *other == kind)?;
+        self.scoring.get(index).map(|system| system.as_ref())
+    }
+    
+    // A press or release that landed in `tier`
+    pub fn record_hit(&mut self, tier: usize, windows: &JudgementWindows) {
+        let judgement = &windows.tiers[tier];
+        if self.counts.len() < windows.tiers.len() {
+            self.counts.resize(windows.tiers.len(), 0);
+        }
+        self.counts[tier] += 1;
+        self.points += judgement.score as u64;
+        self.max_points += windows.max_score() as u64;
+        if judgement.breaks_combo {
+            self.streak = 0; // Sloppy tiers still score but reset the streak
+        } else {
+            self.streak += 1;
+            self.max_combo = self.max_combo.max(self.streak);
+        }
+        for system in &mut self.scoring {
+            system.hit(tier, self.streak, windows);
+        }
+    }
+    
+    pub fn record_miss(&mut self, windows: &JudgementWindows) {
+        self.misses += 1;
+        self.streak = 0;
+        self.max_points += windows.max_score() as u64;
+        for system in &mut self.scoring {
+            system.miss();
+        }
+    }
+    
+    pub fn record_drop(&mut self, windows: &JudgementWindows) {
+        self.drops += 1;
+        self.streak = 0;
+        self.max_points += windows.max_score() as u64;
+        for system in &mut self.scoring {
+            system.miss();
         }
     }
+    
+    pub fn record_mine(&mut self) {
+        self.mines_hit += 1;
+        for system in &mut self.scoring {
+            system.mine();
+        }
+    }
+    
+    // Percentage of the accuracy points available so far, 100 before anything is judged
+    pub fn accuracy(&self) -> f32 {
+        if self.max_points == 0 {
+            return 100.0;
+        }
+        (self.points as f64 / self.max_points as f64 * 100.0) as f32
+    }
+    
+    // Every scoring system side by side, e.g. "Points 1200 | Score 987654 | EX 33 | Arcade 2400"
+    pub fn summary(&self) -> String {
+        self.scoring
+            .iter()
+            .map(|system| format!("{} {}", system.name(), system.score()))
+            .collect::<Vec<_>>()
+            .join(" | ")
+    }
+    
+    pub fn grade(&self) -> Grade {
+        Grade::from_accuracy(self.accuracy())
+    }
+}
+
+
+// Which lane keys are down right now; `handle_input` writes it straight from the keyboard, only when a lane changes
+#[derive(Resource, Default)]
+pub struct LaneState {
+    pub held: [bool; LANES.len()],
+}
+
+// One judged press, kept for the timing stats
+#[derive(Clone, Copy, Debug)]
+pub struct HitRecord {
+    pub lane: usize,
+    pub hit_time: f32, // When the note was scheduled to be hit
+    pub offset: f32, // Press time - hit time in seconds, negative when early
+    pub tier: usize, // Index into the JudgementWindows tiers the hit was judged with
+}
+
+// Every hit of the current chart, in the order they were pressed, for the results graphs
+#[derive(Resource, Default)]
+pub struct HitLog {
+    pub hits: Vec<HitRecord>,
+    pub misses: Vec<f32>, // Hit times of the notes that went by unhit
+}
+
+impl HitLog {
+    // Average offset in seconds, positive when hitting late on the whole
+    pub fn mean_offset(&self) -> Option<f32> {
+        if self.hits.is_empty() {
+            return None;
+        }
+        Some(self.hits.iter().map(|hit| hit.offset).sum::<f32>() / self.hits.len() as f32)
+    }
+    
+    // Standard deviation of the offsets in seconds, how consistent the timing was
+    pub fn offset_deviation(&self) -> Option<f32> {
+        let mean = self.mean_offset()?;
+        let variance = self.hits.iter().map(|hit| (hit.offset - mean).powi(2)).sum::<f32>() / self.hits.len() as f32;
+        Some(variance.sqrt())
+    }
 }
 
 #[derive(Resource)]
 pub struct Metronome {
-    pub next_beat_time: f32,
-    pub song_start_time: f32,
+    pub next_click: Beat, // Chart beat of the next click, the tempo map says when it lands
+    pub song_start_time: f32, // Song start the clicks are scheduled against, a restart resyncs them
+    pub is_active: bool,
+    pub audio_handle: Option<Handle<bevy_kira_audio::AudioSource>>, // Click on every counted beat
+    pub accent_handle: Option<Handle<bevy_kira_audio::AudioSource>>, // Click on the first beat of each measure
+    pub volume: f64, // Volume of the metronome channel
+    pub accent_volume: f64, // Extra gain on downbeats, on top of `volume`
+}
+
+// "Note reached the target" pop
+#[derive(Resource)]
+pub struct TargetCue {
     pub is_active: bool,
-    pub audio_handle: Option<Handle<bevy::prelude::AudioSource>>,
-}
\ No newline at end of file
+    pub audio_handle: Option<Handle<bevy_kira_audio::AudioSource>>,
+    pub volume: f64, // Volume of the target cue channel
+}
+
+// Kira channels for the cues, so each one's volume is set separately from the song and keysounds
+#[derive(Resource)]
+pub struct MetronomeChannel;
+
+#[derive(Resource)]
+pub struct TargetCueChannel;
+
+// The chart being played, loaded through the AssetServer so edits hot reload
+#[derive(Resource)]
+pub struct CurrentChart {
+    pub handle: Handle<Chart>,
+    pub pending: bool, // Not started yet, `apply_chart_changes` starts it as soon as it's loaded
+}
+
+// Options on the pause menu, top to bottom
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PauseOption {
+    Resume,
+    Restart,
+    Quit,
+}
+
+impl PauseOption {
+    pub const ALL: [PauseOption; 3] = [PauseOption::Resume, PauseOption::Restart, PauseOption::Quit];
+    
+    pub fn name(&self) -> &'static str {
+        match self {
+            PauseOption::Resume => "Resume",
+            PauseOption::Restart => "Restart",
+            PauseOption::Quit => "Quit",
+        }
+    }
+}
+
+// Pause menu state, reset every time the game is paused
+#[derive(Resource, Default)]
+pub struct PauseMenu {
+    pub selected: usize, // Index into `PauseOption::ALL`
+    pub countdown: Option<Timer>, // Running once Resume is picked, play continues when it's done
+}
+
+// Chart to play next, picked on the song select screen
+#[derive(Resource, Default)]
+pub struct SelectedChart {
+    pub song: usize, // Index into `SongLibrary::songs`
+    pub difficulty: usize, // Index into the song's difficulties
+    pub path: String, // Asset path of the picked chart
+}
+
+// Keysound samples of the current chart, and the background sounds that play on their own
+#[derive(Resource, Default)]
+pub struct KeysoundBank {
+    pub samples: Vec<Handle<bevy_kira_audio::AudioSource>>,
+    pub background: Vec<BackgroundSound>,
+    pub background_index: usize,
+    pub song_start_time: f32, // Start time the background index belongs to, a restart rewinds it
+}
+
+// Gameplay clock; once the song is playing it follows the audio's playback position,
+// so notes stay in sync with the music even when frames hitch
+#[derive(Resource, Default)]
+pub struct SongClock {
+    pub now: f32, // Current gameplay time, on the same scale as `NoteSpawnTimer::song_start_time`
+    pub song_start_time: f32, // Start time the song instance belongs to, a restart plays it again
+    pub audio: Option<Handle<bevy_kira_audio::AudioSource>>, // The chart's song, if it has one
+    pub instance: Option<Handle<bevy_kira_audio::AudioInstance>>,
+    pub last_position: Option<f64>, // Audio position seen last, the clock coasts on frame time until it moves
+}
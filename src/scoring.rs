@@ -0,0 +1,138 @@
+// Scoring systems: every run is scored by all of them at once so they can be compared,
+// the settings pick the one shown while playing
+
+use serde::{Deserialize, Serialize};
+use crate::constants::*;
+use crate::judgement::JudgementWindows;
+
+pub trait ScoringSystem: Send + Sync {
+    fn name(&self) -> &'static str;
+    // A press or release judged into `tier`, `combo` is the streak including it
+    fn hit(&mut self, tier: usize, combo: u32, windows: &JudgementWindows);
+    fn miss(&mut self) {}
+    fn mine(&mut self) {}
+    fn score(&self) -> u64;
+}
+
+// Our original points: each tier's score, mines take some away
+#[derive(Default)]
+pub struct PointsScore {
+    score: u64,
+}
+
+impl ScoringSystem for PointsScore {
+    fn name(&self) -> &'static str {
+        "Points"
+    }
+
+    fn hit(&mut self, tier: usize, _combo: u32, windows: &JudgementWindows) {
+        self.score += windows.tiers[tier].score as u64;
+    }
+
+    fn mine(&mut self) {
+        self.score = self.score.saturating_sub(MINE_PENALTY as u64);
+    }
+
+    fn score(&self) -> u64 {
+        self.score
+    }
+}
+
+// Out of 1,000,000 whatever the chart's length, each judgement is worth its share scaled by its tier
+pub struct MillionScore {
+    total_judgements: u32,
+    earned: f64, // Judgements' worth as a fraction of the best tier, summed
+}
+
+impl MillionScore {
+    pub fn new(total_judgements: u32) -> Self {
+        Self { total_judgements, earned: 0.0 }
+    }
+}
+
+impl ScoringSystem for MillionScore {
+    fn name(&self) -> &'static str {
+        "Score"
+    }
+
+    fn hit(&mut self, tier: usize, _combo: u32, windows: &JudgementWindows) {
+        self.earned += windows.tiers[tier].score as f64 / windows.max_score().max(1) as f64;
+    }
+
+    fn score(&self) -> u64 {
+        if self.total_judgements == 0 {
+            return 0;
+        }
+        (MILLION_SCORE_MAX as f64 * self.earned / self.total_judgements as f64).round() as u64
+    }
+}
+
+// DDR style EX score: 3 points for the best tier, one less for each tier after it
+#[derive(Default)]
+pub struct ExScore {
+    score: u64,
+}
+
+impl ScoringSystem for ExScore {
+    fn name(&self) -> &'static str {
+        "EX"
+    }
+
+    fn hit(&mut self, tier: usize, _combo: u32, _windows: &JudgementWindows) {
+        self.score += EX_SCORE_BEST.saturating_sub(tier as u64);
+    }
+
+    fn score(&self) -> u64 {
+        self.score
+    }
+}
+
+// Arcade style: tier scores multiplied by the combo, one step up every ARCADE_COMBO_STEP notes
+#[derive(Default)]
+pub struct ArcadeScore {
+    score: u64,
+}
+
+impl ScoringSystem for ArcadeScore {
+    fn name(&self) -> &'static str {
+        "Arcade"
+    }
+
+    fn hit(&mut self, tier: usize, combo: u32, windows: &JudgementWindows) {
+        let multiplier = (1 + combo / ARCADE_COMBO_STEP).min(ARCADE_MAX_MULTIPLIER);
+        self.score += windows.tiers[tier].score as u64 * multiplier as u64;
+    }
+
+    fn mine(&mut self) {
+        self.score = self.score.saturating_sub(MINE_PENALTY as u64);
+    }
+
+    fn score(&self) -> u64 {
+        self.score
+    }
+}
+
+// Scoring system shown while playing, picked in the settings file
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum ScoringKind {
+    #[default]
+    Points,
+    Million,
+    Ex,
+    Arcade,
+}
+
+impl ScoringKind {
+    pub const ALL: [ScoringKind; 4] = [ScoringKind::Points, ScoringKind::Million, ScoringKind::Ex, ScoringKind::Arcade];
+
+    // A fresh system for a chart with `total_judgements` presses and releases to judge
+    pub fn system(&self, total_judgements: u32) -> Box<dyn ScoringSystem> {
+        match self {
+            ScoringKind::Points => Box::new(PointsScore::default()),
+            ScoringKind::Million => Box::new(MillionScore::new(total_judgements)),
+            ScoringKind::Ex => Box::new(ExScore::default()),
+            ScoringKind::Arcade => Box::new(ArcadeScore::default()),
+        }
+    }
+}
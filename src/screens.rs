@@ -0,0 +1,509 @@
+// Menu screens around the gameplay: title, song select, loading and results
+
+use bevy::asset::LoadState;
+use bevy::prelude::*;
+use crate::{charts::*, components::*, constants::*, resources::*, health::*, judgement::*, library::*, settings::*, states::*, types::*};
+
+// Centered block of text a little below the top of the window
+fn spawn_screen_text(commands: &mut Commands, text: String, font_size: f32, marker: impl Component) {
+    commands.spawn((
+        TextBundle::from_section(
+            text,
+            TextStyle {
+                font_size,
+                color: Color::WHITE,
+                ..default()
+            },
+        )
+        .with_style(Style {
+            position_type: PositionType::Absolute,
+            top: Val::Px(150.0),
+            width: Val::Percent(100.0),
+            justify_content: JustifyContent::Center,
+            ..default()
+        })
+        .with_text_justify(JustifyText::Center),
+        marker,
+    ));
+}
+
+// Take a screen's entities down when leaving it
+pub fn despawn_screen<T: Component>(mut commands: Commands, query: Query<Entity, With<T>>) {
+    for entity in &query {
+        commands.entity(entity).despawn_recursive();
+    }
+}
+
+pub fn spawn_title(mut commands: Commands) {
+    spawn_screen_text(&mut commands, "RUSTY RHYTHM\n\nPress Enter to start\nPress ESC to exit".to_string(), 48.0, TitleUI);
+}
+
+pub fn title_input(
+    keys: Res<ButtonInput<KeyCode>>,
+    mut next_state: ResMut<NextState<GameState>>,
+    mut exit: EventWriter<AppExit>,
+) {
+    if keys.just_pressed(KeyCode::Enter) {
+        next_state.set(GameState::SongSelect);
+    }
+    if keys.just_pressed(KeyCode::Escape) {
+        exit.send(AppExit::Success);
+    }
+}
+
+pub fn spawn_song_select(mut commands: Commands, library: Res<SongLibrary>, selected: Res<SelectedChart>) {
+    let (list, details) = song_select_sections(&library, &selected);
+    commands
+        .spawn((
+            NodeBundle {
+                style: Style {
+                    position_type: PositionType::Absolute,
+                    width: Val::Percent(100.0),
+                    height: Val::Percent(100.0),
+                    padding: UiRect::all(Val::Px(20.0)),
+                    column_gap: Val::Px(20.0),
+                    justify_content: JustifyContent::SpaceBetween,
+                    ..default()
+                },
+                ..default()
+            },
+            SongSelectUI,
+        ))
+        .with_children(|root| {
+            root.spawn((TextBundle::from_sections(list), SongListText));
+            root.spawn((TextBundle::from_sections(details), SongDetailsText));
+        });
+}
+
+// Up and down pick a song, left and right its difficulty, Enter plays it
+pub fn song_select_input(
+    keys: Res<ButtonInput<KeyCode>>,
+    library: Res<SongLibrary>,
+    mut selected: ResMut<SelectedChart>,
+    mut next_state: ResMut<NextState<GameState>>,
+) {
+    if keys.just_pressed(KeyCode::Escape) {
+        next_state.set(GameState::Title);
+    }
+    let count = library.songs.len();
+    if count == 0 {
+        return;
+    }
+    // Only touch the selection on a key press, so the screen is redrawn when something changed
+    if keys.just_pressed(KeyCode::ArrowUp) {
+        selected.song = (selected.song.min(count - 1) + count - 1) % count;
+    }
+    if keys.just_pressed(KeyCode::ArrowDown) {
+        selected.song = (selected.song + 1) % count;
+    }
+    let song = &library.songs[selected.song.min(count - 1)];
+    let last_difficulty = song.difficulties.len().saturating_sub(1);
+    if keys.just_pressed(KeyCode::ArrowLeft) {
+        selected.difficulty = selected.difficulty.saturating_sub(1);
+    }
+    if keys.just_pressed(KeyCode::ArrowRight) {
+        selected.difficulty += 1;
+    }
+    if selected.difficulty > last_difficulty {
+        selected.difficulty = last_difficulty;
+    }
+    
+    if keys.just_pressed(KeyCode::Enter) {
+        if let Some(difficulty) = song.difficulties.get(selected.difficulty) {
+            info!("Picked {} [{}]", song.title, difficulty.name);
+            selected.path = difficulty.path.clone();
+            next_state.set(GameState::Loading);
+        }
+    }
+}
+
+pub fn update_song_select(
+    library: Res<SongLibrary>,
+    selected: Res<SelectedChart>,
+    mut list_query: Query<&mut Text, (With<SongListText>, Without<SongDetailsText>)>,
+    mut details_query: Query<&mut Text, (With<SongDetailsText>, Without<SongListText>)>,
+) {
+    if !selected.is_changed() && !library.is_changed() {
+        return;
+    }
+    let (list, details) = song_select_sections(&library, &selected);
+    if let Ok(mut text) = list_query.get_single_mut() {
+        text.sections = list;
+    }
+    if let Ok(mut text) = details_query.get_single_mut() {
+        text.sections = details;
+    }
+}
+
+// The scrolling song list, and the picked song's details and difficulties
+fn song_select_sections(library: &SongLibrary, selected: &SelectedChart) -> (Vec<TextSection>, Vec<TextSection>) {
+    let style = |font_size: f32, color: Color| TextStyle { font_size, color, ..default() };
+    let plain = Color::srgb(0.9, 0.9, 0.9);
+    let highlight = Color::srgb(1.0, 0.8, 0.2);
+    let mut list = vec![TextSection::new("SONG SELECT\n\n", style(32.0, Color::WHITE))];
+    
+    let Some(song) = library.songs.get(selected.song.min(library.songs.len().saturating_sub(1))) else {
+        list.push(TextSection::new(
+            format!("No songs found in {}/{}\n\nPress ESC to go back", ASSETS_DIR, SONGS_DIR),
+            style(20.0, plain),
+        ));
+        return (list, Vec::new());
+    };
+    
+    // Keep the picked song in the middle of the list once there's more than fits
+    let first = selected.song.saturating_sub(SONG_LIST_ROWS / 2).min(library.songs.len().saturating_sub(SONG_LIST_ROWS));
+    if first > 0 {
+        list.push(TextSection::new("  ...\n", style(20.0, plain)));
+    }
+    for (index, entry) in library.songs.iter().enumerate().skip(first).take(SONG_LIST_ROWS) {
+        let picked = index == selected.song;
+        list.push(TextSection::new(
+            format!("{}{}\n", if picked { "> " } else { "  " }, entry.title),
+            style(22.0, if picked { highlight } else { plain }),
+        ));
+    }
+    if first + SONG_LIST_ROWS < library.songs.len() {
+        list.push(TextSection::new("  ...\n", style(20.0, plain)));
+    }
+    list.push(TextSection::new("\nUp/Down: song | Left/Right: difficulty\nEnter: play | ESC: back", style(16.0, plain)));
+    
+    let mut details = vec![
+        TextSection::new(format!("{}\n", song.title), style(28.0, Color::WHITE)),
+        TextSection::new(format!("{}\n\n", song.artist), style(20.0, plain)),
+        TextSection::new(format!("BPM: {}\nLength: {}\n\n", song.bpm_label(), song.length_label()), style(20.0, plain)),
+    ];
+    for (index, difficulty) in song.difficulties.iter().enumerate() {
+        let picked = index == selected.difficulty;
+        details.push(TextSection::new(
+            format!("{}{} ({}) - {} notes\n", if picked { "> " } else { "  " }, difficulty.name, difficulty.level, difficulty.notes),
+            style(20.0, if picked { highlight } else { plain }),
+        ));
+    }
+    (list, details)
+}
+
+pub fn spawn_loading(mut commands: Commands) {
+    spawn_screen_text(&mut commands, "Loading...".to_string(), 32.0, LoadingUI);
+}
+
+// Start playing once the chart has been started and its song is ready, back to the song select if the chart
+// can't be loaded
+pub fn finish_loading(
+    current_chart: Res<CurrentChart>,
+    clock: Res<SongClock>,
+    asset_server: Res<AssetServer>,
+    mut next_state: ResMut<NextState<GameState>>,
+) {
+    if matches!(asset_server.load_state(&current_chart.handle), LoadState::Failed(_)) {
+        next_state.set(GameState::SongSelect);
+        return;
+    }
+    if current_chart.pending {
+        return;
+    }
+    // A song that fails to load just leaves the chart silent
+    let song_ready = clock.audio.as_ref().is_none_or(|song| {
+        matches!(asset_server.load_state(song), LoadState::Loaded | LoadState::Failed(_))
+    });
+    if song_ready {
+        next_state.set(GameState::Playing);
+    }
+}
+
+// Score, accuracy and judgement breakdown on the left, the timing graphs on the right
+pub fn spawn_results(
+    mut commands: Commands,
+    game_score: Res<GameScore>,
+    hit_log: Res<HitLog>,
+    health: Res<Health>,
+    windows: Res<JudgementWindows>,
+    settings: Res<Settings>,
+    timer: Res<NoteSpawnTimer>,
+    tempo_map: Res<TempoMap>,
+    current_chart: Option<Res<CurrentChart>>,
+    charts: Res<Assets<Chart>>,
+) {
+    let title = current_chart
+        .and_then(|current| charts.get(&current.handle))
+        .map_or(String::new(), |chart| chart.metadata.title.clone());
+    let style = |font_size: f32, color: Color| TextStyle { font_size, color, ..default() };
+    let plain = Color::srgb(0.9, 0.9, 0.9);
+    let grade = game_score.grade();
+    
+    let mut sections = vec![
+        TextSection::new(
+            if health.failed { "FAILED\n" } else { "RESULTS\n" },
+            style(40.0, if health.failed { Color::srgb(1.0, 0.1, 0.1) } else { Color::WHITE }),
+        ),
+        TextSection::new(format!("{}\n\n", title), style(24.0, plain)),
+        TextSection::new(format!("{}  ", grade.name()), style(56.0, grade.color())),
+        TextSection::new(format!("{:.2}%\n\n", game_score.accuracy()), style(32.0, plain)),
+    ];
+    // Every scoring system side by side, the one picked in the settings first
+    let selected = game_score.system(settings.scoring).map(|system| system.name());
+    for system in &game_score.scoring {
+        let color = if Some(system.name()) == selected { Color::srgb(1.0, 0.8, 0.2) } else { plain };
+        sections.push(TextSection::new(format!("{}: {}\n", system.name(), system.score()), style(20.0, color)));
+    }
+    sections.push(TextSection::new("\n", style(20.0, plain)));
+    for (tier, count) in windows.tiers.iter().zip(&game_score.counts) {
+        sections.push(TextSection::new(format!("{}: {}\n", tier.name, count), style(20.0, tier.color())));
+    }
+    sections.push(TextSection::new(
+        format!(
+            "MISS: {}\nDROPPED: {}\nMines hit: {}\n\nMax combo: {}/{}\n",
+            game_score.misses, game_score.drops, game_score.mines_hit, game_score.max_combo, game_score.total_judgements,
+        ),
+        style(20.0, plain),
+    ));
+    if let (Some(mean), Some(deviation)) = (hit_log.mean_offset(), hit_log.offset_deviation()) {
+        sections.push(TextSection::new(
+            format!("Mean: {:+.1}ms  Deviation: {:.1}ms\n", mean * 1000.0, deviation * 1000.0),
+            style(20.0, plain),
+        ));
+    }
+    sections.push(TextSection::new("\nPress Enter to continue", style(20.0, plain)));
+    
+    // Graphs span the whole chart, whether or not the run got that far
+    let last_beat = timer.current_pattern.iter().map(|note| note.end_beat.unwrap_or(note.beat)).max();
+    let song_length = tempo_map.beat_to_seconds(last_beat.unwrap_or(Beat::ZERO)).max(1.0);
+    
+    commands
+        .spawn((
+            NodeBundle {
+                style: Style {
+                    position_type: PositionType::Absolute,
+                    width: Val::Percent(100.0),
+                    height: Val::Percent(100.0),
+                    padding: UiRect::all(Val::Px(20.0)),
+                    column_gap: Val::Px(20.0),
+                    justify_content: JustifyContent::SpaceBetween,
+                    ..default()
+                },
+                ..default()
+            },
+            ResultsUI,
+        ))
+        .with_children(|root| {
+            root.spawn(TextBundle::from_sections(sections));
+            root.spawn(NodeBundle {
+                style: Style {
+                    flex_direction: FlexDirection::Column,
+                    row_gap: Val::Px(12.0),
+                    ..default()
+                },
+                ..default()
+            })
+            .with_children(|graphs| {
+                graphs.spawn(TextBundle::from_section("Late", style(14.0, plain)));
+                spawn_scatter_plot(graphs, &hit_log, &windows, timer.song_start_time, song_length);
+                graphs.spawn(TextBundle::from_section("Early", style(14.0, plain)));
+                spawn_histogram(graphs, &hit_log, &windows);
+                graphs.spawn(TextBundle::from_section(
+                    format!("-{0:.0}ms    early / late    +{0:.0}ms", windows.widest() * 1000.0),
+                    style(14.0, plain),
+                ));
+            });
+        });
+    debug!("{}", game_score.summary());
+}
+
+// Plain colored box placed inside a graph, measured from its top left corner
+fn graph_box(left: f32, top: f32, width: f32, height: f32, color: Color) -> NodeBundle {
+    NodeBundle {
+        style: Style {
+            position_type: PositionType::Absolute,
+            left: Val::Px(left),
+            top: Val::Px(top),
+            width: Val::Px(width),
+            height: Val::Px(height),
+            ..default()
+        },
+        background_color: color.into(),
+        ..default()
+    }
+}
+
+fn graph_background(height: f32) -> NodeBundle {
+    NodeBundle {
+        style: Style {
+            width: Val::Px(RESULTS_GRAPH_WIDTH),
+            height: Val::Px(height),
+            ..default()
+        },
+        background_color: Color::srgb(0.1, 0.1, 0.12).into(),
+        ..default()
+    }
+}
+
+// Every hit's offset (late up, early down) over song time, with a line at each window and misses in red
+fn spawn_scatter_plot(parent: &mut ChildBuilder, hit_log: &HitLog, windows: &JudgementWindows, song_start_time: f32, song_length: f32) {
+    let range = windows.widest().max(0.001);
+    let y = |offset: f32| (0.5 - offset.clamp(-range, range) / range / 2.0) * SCATTER_HEIGHT;
+    let x = |hit_time: f32| ((hit_time - song_start_time) / song_length).clamp(0.0, 1.0) * RESULTS_GRAPH_WIDTH;
+    
+    parent.spawn(graph_background(SCATTER_HEIGHT)).with_children(|plot| {
+        for tier in &windows.tiers {
+            let window = tier.window_ms / 1000.0;
+            for offset in [-window, window] {
+                plot.spawn(graph_box(0.0, y(offset), RESULTS_GRAPH_WIDTH, 1.0, tier.color().with_alpha(0.3)));
+            }
+        }
+        plot.spawn(graph_box(0.0, y(0.0), RESULTS_GRAPH_WIDTH, 1.0, Color::srgba(1.0, 1.0, 1.0, 0.6)));
+        
+        for &miss in &hit_log.misses {
+            plot.spawn(graph_box(x(miss), 0.0, 1.0, SCATTER_HEIGHT, Color::srgba(1.0, 0.1, 0.1, 0.5)));
+        }
+        for hit in &hit_log.hits {
+            let color = windows.tiers.get(hit.tier).map_or(Color::WHITE, |tier| tier.color());
+            plot.spawn(graph_box(x(hit.hit_time) - 2.0, y(hit.offset) - 2.0, 4.0, 4.0, color));
+        }
+    });
+}
+
+// How many hits landed at each offset, early on the left and late on the right
+fn spawn_histogram(parent: &mut ChildBuilder, hit_log: &HitLog, windows: &JudgementWindows) {
+    let range = windows.widest().max(0.001);
+    let bin_width = 2.0 * range / HISTOGRAM_BINS as f32;
+    let mut bins = [0u32; HISTOGRAM_BINS];
+    for hit in &hit_log.hits {
+        let bin = ((hit.offset + range) / bin_width).floor().clamp(0.0, HISTOGRAM_BINS as f32 - 1.0);
+        bins[bin as usize] += 1;
+    }
+    let tallest = bins.iter().copied().max().unwrap_or(0).max(1);
+    let bar_width = RESULTS_GRAPH_WIDTH / HISTOGRAM_BINS as f32;
+    
+    parent.spawn(graph_background(HISTOGRAM_HEIGHT)).with_children(|plot| {
+        plot.spawn(graph_box(RESULTS_GRAPH_WIDTH / 2.0, 0.0, 1.0, HISTOGRAM_HEIGHT, Color::srgba(1.0, 1.0, 1.0, 0.3)));
+        for (index, &count) in bins.iter().enumerate() {
+            if count == 0 {
+                continue;
+            }
+            // Bars take the color of the tier their middle falls in
+            let center = -range + (index as f32 + 0.5) * bin_width;
+            let color = windows.judge(center).and_then(|tier| windows.tiers.get(tier)).map_or(Color::WHITE, |tier| tier.color());
+            let height = count as f32 / tallest as f32 * HISTOGRAM_HEIGHT;
+            plot.spawn(graph_box(index as f32 * bar_width + 1.0, HISTOGRAM_HEIGHT - height, bar_width - 2.0, height, color));
+        }
+    });
+}
+
+pub fn results_input(keys: Res<ButtonInput<KeyCode>>, mut next_state: ResMut<NextState<GameState>>) {
+    if keys.any_just_pressed([KeyCode::Enter, KeyCode::Escape]) {
+        next_state.set(GameState::SongSelect);
+    }
+}
+
+pub fn spawn_pause_menu(mut commands: Commands) {
+    commands.insert_resource(PauseMenu::default());
+    commands
+        .spawn((
+            NodeBundle {
+                style: Style {
+                    position_type: PositionType::Absolute,
+                    width: Val::Percent(100.0),
+                    height: Val::Percent(100.0),
+                    justify_content: JustifyContent::Center,
+                    align_items: AlignItems::Center,
+                    ..default()
+                },
+                background_color: Color::srgba(0.0, 0.0, 0.0, 0.7).into(),
+                z_index: ZIndex::Global(10),
+                ..default()
+            },
+            PauseMenuUI,
+        ))
+        .with_children(|overlay| {
+            overlay.spawn((
+                TextBundle::from_sections(
+                    // A heading, then one section per option
+                    std::iter::repeat_n(
+                        TextSection::new("", TextStyle { font_size: 40.0, color: Color::WHITE, ..default() }),
+                        PauseOption::ALL.len() + 1,
+                    ),
+                )
+                .with_text_justify(JustifyText::Center),
+                PauseMenuText,
+            ));
+        });
+}
+
+// Up and down pick an option and Enter takes it, ESC resumes
+pub fn pause_menu_input(
+    keys: Res<ButtonInput<KeyCode>>,
+    mut menu: ResMut<PauseMenu>,
+    mut next_state: ResMut<NextState<GameState>>,
+) {
+    if menu.countdown.is_some() {
+        return;
+    }
+    let count = PauseOption::ALL.len();
+    if keys.just_pressed(KeyCode::ArrowUp) {
+        menu.selected = (menu.selected + count - 1) % count;
+    }
+    if keys.just_pressed(KeyCode::ArrowDown) {
+        menu.selected = (menu.selected + 1) % count;
+    }
+    
+    let picked = if keys.just_pressed(KeyCode::Escape) {
+        Some(PauseOption::Resume)
+    } else if keys.just_pressed(KeyCode::Enter) {
+        Some(PauseOption::ALL[menu.selected])
+    } else {
+        None
+    };
+    match picked {
+        Some(PauseOption::Resume) => {
+            menu.countdown = Some(Timer::from_seconds(RESUME_COUNTDOWN, TimerMode::Once));
+        }
+        Some(PauseOption::Restart) => {
+            info!("Restarting the chart");
+            next_state.set(GameState::Loading);
+        }
+        Some(PauseOption::Quit) => {
+            info!("Quit the song");
+            next_state.set(GameState::SongSelect);
+        }
+        None => {}
+    }
+}
+
+// Tick the resume countdown and go back to playing once it runs out
+pub fn resume_countdown(
+    time: Res<Time>,
+    mut menu: ResMut<PauseMenu>,
+    mut next_state: ResMut<NextState<GameState>>,
+) {
+    let Some(countdown) = menu.countdown.as_mut() else {
+        return;
+    };
+    countdown.tick(time.delta());
+    if countdown.finished() {
+        next_state.set(GameState::Playing);
+    }
+}
+
+pub fn update_pause_menu(menu: Res<PauseMenu>, mut text_query: Query<&mut Text, With<PauseMenuText>>) {
+    if !menu.is_changed() {
+        return;
+    }
+    let Ok(mut text) = text_query.get_single_mut() else {
+        return;
+    };
+    
+    if let Some(countdown) = &menu.countdown {
+        text.sections[0].value = format!("{}", countdown.remaining_secs().ceil().max(1.0));
+        text.sections[0].style.font_size = 96.0;
+        for section in &mut text.sections[1..] {
+            section.value.clear();
+        }
+        return;
+    }
+    text.sections[0].value = "PAUSED\n\n".to_string();
+    for (index, option) in PauseOption::ALL.iter().enumerate() {
+        let section = &mut text.sections[index + 1];
+        let selected = index == menu.selected;
+        section.value = format!("{}{}\n", if selected { "> " } else { "" }, option.name());
+        section.style.color = if selected { Color::srgb(1.0, 0.8, 0.2) } else { Color::WHITE };
+    }
+}
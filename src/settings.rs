@@ -0,0 +1,55 @@
+// Player settings, read from a YAML file next to the assets
+
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+use crate::health::LifeSettings;
+use crate::judgement::{JudgementPreset, JudgementWindows};
+use crate::scoring::ScoringKind;
+
+#[derive(Resource, Serialize, Deserialize, Clone, Debug, Default, PartialEq)]
+#[serde(default)]
+pub struct Settings {
+    pub judgement: JudgementPreset,
+    pub release_window_ms: Option<f32>, // How early holds can be let go of, the preset's widest window if unset
+    pub roll_window_ms: Option<f32>, // How long rolls can go without a tap, 500ms if unset
+    pub life: LifeSettings,
+    pub scoring: ScoringKind, // Scoring system shown while playing, the others are still kept for the results
+}
+
+impl Settings {
+    pub fn judgement_windows(&self) -> JudgementWindows {
+        let mut windows = self.judgement.windows();
+        if let Some(release_window_ms) = self.release_window_ms {
+            windows.release_window_ms = release_window_ms.max(0.0);
+        }
+        if let Some(roll_window_ms) = self.roll_window_ms {
+            windows.roll_window_ms = roll_window_ms.max(0.0);
+        }
+        windows
+    }
+    
+    // Missing files and bad values fall back to the defaults so the game always starts
+    pub fn load(path: impl AsRef<Path>) -> Settings {
+        let path = path.as_ref();
+        let source = match std::fs::read_to_string(path) {
+            Ok(source) => source,
+            Err(error) => {
+                warn!("Couldn't read settings from {}: {}, using the defaults", path.display(), error);
+                return Settings::default();
+            }
+        };
+        let mut settings: Settings = match serde_yaml::from_str(&source) {
+            Ok(settings) => settings,
+            Err(error) => {
+                warn!("Invalid settings in {}: {}, using the defaults", path.display(), error);
+                return Settings::default();
+            }
+        };
+        if let Err(error) = settings.judgement.validate() {
+            warn!("Invalid judgement windows in {}: {}, using the {} windows", path.display(), error, JudgementWindows::default().name);
+            settings.judgement = JudgementPreset::default();
+        }
+        settings
+    }
+}
@@ -0,0 +1,26 @@
+// Screens the game moves between: title -> song select -> loading -> playing (and paused) -> results
+
+use bevy::prelude::*;
+
+#[derive(States, Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub enum GameState {
+    #[default]
+    Title,
+    SongSelect,
+    Loading, // Waiting on the picked chart and its song
+    Playing,
+    Paused,
+    Results,
+}
+
+// Playing or paused, the playfield and notes stay up across a pause
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct InGame;
+
+impl ComputedStates for InGame {
+    type SourceStates = GameState;
+
+    fn compute(state: GameState) -> Option<Self> {
+        matches!(state, GameState::Playing | GameState::Paused).then_some(InGame)
+    }
+}
@@ -1,12 +1,22 @@
+use bevy::asset::AssetLoadFailedEvent;
 use bevy::prelude::*;
+use bevy::sprite::Anchor;
+use bevy_kira_audio::{Audio, AudioChannel, AudioControl, AudioInstance, AudioSource, AudioTween};
+use std::collections::HashSet;
+use std::path::Path;
 use std::time::Duration;
-use crate::{components::*, resources::*, types::*, constants::*};
+use crate::{charts::*, components::*, resources::*, types::*, constants::*, events::*, health::*, judgement::*, library::*, settings::*, states::*};
 
 // Startup system for initializing the game
 pub fn setup(mut commands: Commands) {
     // Spawn a camera
     commands.spawn(Camera2dBundle::default());
     
+    info!("Rusty Rhythm initialized");
+}
+
+// Lanes, targets, life bar and score displays, up for as long as a chart is being played
+pub fn spawn_playfield(mut commands: Commands) {
     // Define the 4 colors for the lanes
     let lane_colors = [
         Color::srgb(1.0, 0.2, 0.2), // Red
@@ -30,6 +40,7 @@ pub fn setup(mut commands: Commands) {
                 ..default()
             },
             Target { lane: i },
+            Playfield,
         ));
         
         // Create the outline for target squares
@@ -48,6 +59,7 @@ pub fn setup(mut commands: Commands) {
                 border_type: BorderType::Top,
                 original_size: Vec2::new(NOTE_SIZE + 4.0, 4.0),
             },
+            Playfield,
         ));
         
         commands.spawn((
@@ -65,6 +77,7 @@ pub fn setup(mut commands: Commands) {
                 border_type: BorderType::Bottom,
                 original_size: Vec2::new(NOTE_SIZE + 4.0, 4.0),
             },
+            Playfield,
         ));
         
         commands.spawn((
@@ -82,6 +95,7 @@ pub fn setup(mut commands: Commands) {
                 border_type: BorderType::Left,
                 original_size: Vec2::new(4.0, NOTE_SIZE),
             },
+            Playfield,
         ));
         
         commands.spawn((
@@ -99,13 +113,29 @@ pub fn setup(mut commands: Commands) {
                 border_type: BorderType::Right,
                 original_size: Vec2::new(4.0, NOTE_SIZE),
             },
+            Playfield,
         ));
     }
     
+    // Create visual metronome indicator (top center of screen), the metronome lights it on each click
+    commands.spawn((
+        SpriteBundle {
+            sprite: Sprite {
+                color: Color::NONE, // Start transparent
+                custom_size: Some(Vec2::new(20.0, 20.0)),
+                ..default()
+            },
+            transform: Transform::from_xyz(0.0, WINDOW_HEIGHT/2.0 - 50.0, 1.0),
+            ..default()
+        },
+        MetronomeFlash,
+        Playfield,
+    ));
+    
     // Add instructional text
-    commands.spawn(
+    commands.spawn((
         TextBundle::from_section(
-            "Rusty Rhythm - Musical Note Colors!\nRED: Quarter/Half/Whole (on beat) | BLUE: Eighth (upbeats)\nGREEN: Sixteenth (subdivisions) | YELLOW: Triplets (complex)\nPress J, K, L, ; to hit the lanes | Press ESC to exit",
+            "Rusty Rhythm - Musical Note Colors!\nRED: Quarter/Half/Whole (on beat) | BLUE: Eighth (upbeats)\nGREEN: Sixteenth (subdivisions) | YELLOW: Triplets (complex)\nPress J, K, L, ; to hit the lanes | Press ESC to pause",
             TextStyle {
                 font_size: 20.0,
                 color: Color::WHITE,
@@ -118,7 +148,8 @@ pub fn setup(mut commands: Commands) {
             left: Val::Px(10.0),
             ..default()
         }),
-    );
+        Playfield,
+    ));
     
     // Add score display (top right)
     commands.spawn((
@@ -137,6 +168,7 @@ pub fn setup(mut commands: Commands) {
             ..default()
         }),
         ScoreUI,
+        Playfield,
     ));
     
     // Add streak display (center of screen)
@@ -159,268 +191,644 @@ pub fn setup(mut commands: Commands) {
             ..default()
         }),
         StreakUI,
+        Playfield,
     ));
     
-    println!("Rusty Rhythm initialized! 🎵🦀");
+    // Life bar to the right of the lanes, the fill grows up from the target line
+    let life_bar_bottom = TARGET_Y - NOTE_SIZE / 2.0;
+    commands.spawn((
+        SpriteBundle {
+            sprite: Sprite {
+                color: Color::srgb(0.15, 0.15, 0.15),
+                custom_size: Some(Vec2::new(LIFE_BAR_WIDTH + 4.0, LIFE_BAR_HEIGHT + 4.0)),
+                anchor: Anchor::BottomCenter,
+                ..default()
+            },
+            transform: Transform::from_xyz(LIFE_BAR_X, life_bar_bottom - 2.0, 0.4),
+            ..default()
+        },
+        Playfield,
+    ));
+    commands.spawn((
+        SpriteBundle {
+            sprite: Sprite {
+                color: Color::srgb(0.2, 1.0, 0.2),
+                custom_size: Some(Vec2::new(LIFE_BAR_WIDTH, LIFE_BAR_HEIGHT)),
+                anchor: Anchor::BottomCenter,
+                ..default()
+            },
+            transform: Transform::from_xyz(LIFE_BAR_X, life_bar_bottom, 0.5),
+            ..default()
+        },
+        LifeBarFill,
+        Playfield,
+    ));
+    
+    // Judgement counters, max combo and accuracy (under the score)
+    commands.spawn((
+        TextBundle::from_sections([
+            TextSection::new(
+                "",
+                TextStyle {
+                    font_size: COMBO_FONT_SIZE,
+                    color: Color::srgb(0.9, 0.9, 0.9),
+                    ..default()
+                },
+            ),
+            TextSection::new(
+                "",
+                TextStyle {
+                    font_size: COMBO_FONT_SIZE,
+                    color: Grade::SS.color(),
+                    ..default()
+                },
+            ),
+        ])
+        .with_style(Style {
+            position_type: PositionType::Absolute,
+            top: Val::Px(50.0),
+            right: Val::Px(10.0),
+            ..default()
+        }),
+        JudgementCountsUI,
+        Playfield,
+    ));
+}
+
+// Take down everything a chart left on screen and stop its song, once it's quit or over
+pub fn teardown_playfield(
+    mut commands: Commands,
+    mut clock: ResMut<SongClock>,
+    mut audio_instances: ResMut<Assets<AudioInstance>>,
+    entity_query: Query<Entity, Or<(
+        With<Playfield>,
+        With<Note>,
+        With<ScoreText>,
+        With<PressAnimation>,
+        With<FailedUI>,
+        With<MetronomeFlash>,
+        With<FlashTimer>,
+    )>>,
+) {
+    for entity in &entity_query {
+        commands.entity(entity).despawn_recursive();
+    }
+    if let Some(instance) = clock.instance.take().and_then(|handle| audio_instances.get_mut(&handle)) {
+        instance.stop(AudioTween::default());
+    }
+    clock.audio = None;
 }
 
 // Input handling system
+// Lane input keys (J, K, L, Semicolon), far left to far right
+const LANE_KEYS: [KeyCode; 4] = [KeyCode::KeyJ, KeyCode::KeyK, KeyCode::KeyL, KeyCode::Semicolon];
+
+// Turn the lane keys into press and release events, and keep track of which ones are held
 pub fn handle_input(
     mut commands: Commands,
     keys: Res<ButtonInput<KeyCode>>,
-    mut exit: EventWriter<AppExit>,
-    note_query: Query<(Entity, &Transform, &Note)>,
-    mut game_score: ResMut<GameScore>,
+    mut next_state: ResMut<NextState<GameState>>,
+    mut lane_inputs: EventWriter<LaneInput>,
+    mut lanes: ResMut<LaneState>,
+    clock: Res<SongClock>,
 ) {
     if keys.just_pressed(KeyCode::Escape) {
-        exit.send(AppExit::Success);
+        info!("Paused at {:.3}s", clock.now);
+        next_state.set(GameState::Paused);
     }
     
-    // Handle lane input keys (J, K, L, Semicolon)
-    let lane_keys = [
-        (KeyCode::KeyJ, 0),     // Far left (Red)
-        (KeyCode::KeyK, 1),     // Second (Green)
-        (KeyCode::KeyL, 2),     // Third (Blue)
-        (KeyCode::Semicolon, 3), // Far right (Yellow)
-    ];
-    
-    for (key, lane) in lane_keys {
+    for (lane, key) in LANE_KEYS.into_iter().enumerate() {
         if keys.just_pressed(key) {
             // Start button press animation for this lane
             commands.spawn(PressAnimation {
                 timer: Timer::new(Duration::from_secs_f32(0.2), TimerMode::Once),
                 lane,
             });
-            
-            // Check for note hits in this lane
-            check_note_hit(&mut commands, lane, &note_query, &mut game_score);
+            lane_inputs.send(LaneInput { lane, time: clock.now, pressed: true });
+        }
+        if keys.just_released(key) {
+            lane_inputs.send(LaneInput { lane, time: clock.now, pressed: false });
+        }
+        // Only write on a change so LaneState doesn't show up as changed every frame
+        let held = keys.pressed(key);
+        if lanes.held[lane] != held {
+            lanes.held[lane] = held;
+        }
+    }
+}
+
+// Judge lane presses and releases against the notes on screen
+pub fn judge_lane_input(
+    mut commands: Commands,
+    mut lane_inputs: EventReader<LaneInput>,
+    note_query: Query<(Entity, &Note), (Without<Holding>, Without<HoldDropped>)>,
+    mut roll_query: Query<(&Note, &mut Holding)>,
+    windows: Res<JudgementWindows>,
+    mut game_score: ResMut<GameScore>,
+    mut hit_log: ResMut<HitLog>,
+    mut health: ResMut<Health>,
+    keysounds: Res<KeysoundBank>,
+    audio: Res<Audio>,
+) {
+    // Notes hit earlier this frame, their Holding/despawn commands haven't been applied yet
+    let mut judged: HashSet<Entity> = HashSet::new();
+    for input in lane_inputs.read() {
+        // Tapping a lane with a roll going only keeps the roll alive
+        if input.pressed {
+            let roll = roll_query.iter_mut().find(|(note, _)| note.lane == input.lane && note.kind == NoteKind::Roll);
+            if let Some((_, mut holding)) = roll {
+                holding.last_press = input.time;
+                continue;
+            }
         }
+        check_note_hit(&mut commands, *input, &note_query, &mut judged, &windows, &mut game_score, &mut hit_log, &mut health, &keysounds, &audio);
     }
 }
 
-// Helper function for hit detection
+// Helper function for hit detection - judges the press (or release) by how far it is from the note's hit time
 fn check_note_hit(
     commands: &mut Commands,
-    pressed_lane: usize,
-    note_query: &Query<(Entity, &Transform, &Note)>,
+    input: LaneInput,
+    note_query: &Query<(Entity, &Note), (Without<Holding>, Without<HoldDropped>)>,
+    judged: &mut HashSet<Entity>,
+    windows: &JudgementWindows,
     game_score: &mut ResMut<GameScore>,
+    hit_log: &mut HitLog,
+    health: &mut Health,
+    keysounds: &KeysoundBank,
+    audio: &Audio,
 ) {
-    let mut best_hit: Option<(Entity, f32)> = None; // (entity, overlap_percentage)
-    let hit_zone_height = 100.0;
-    
-    for (entity, transform, note) in note_query {
-        if note.lane == pressed_lane {
-            let note_y = transform.translation.y;
-            let target_y = TARGET_Y;
-            
-            // Check if note is within hit zone
-            let hit_zone_top = target_y + hit_zone_height / 2.0;
-            let hit_zone_bottom = target_y - hit_zone_height / 2.0;
-            
-            if note_y <= hit_zone_top && note_y >= hit_zone_bottom {
-                // Calculate overlap percentage
-                let note_center_distance = (note_y - target_y).abs();
-                let max_distance = NOTE_SIZE / 2.0; // Half the note size
-                
-                let overlap_percentage = if note_center_distance <= max_distance {
-                    // Note is overlapping with target
-                    let overlap = max_distance - note_center_distance;
-                    (overlap / max_distance) * 100.0
-                } else {
-                    // Note is in hit zone but not overlapping
-                    0.0
-                };
-                
-                // Keep track of the best hit (highest overlap)
-                if let Some((_, current_best)) = best_hit {
-                    if overlap_percentage > current_best {
-                        best_hit = Some((entity, overlap_percentage));
-                    }
-                } else {
-                    best_hit = Some((entity, overlap_percentage));
-                }
-            }
+    // The earliest note in this lane whose window the press falls in
+    let mut best_hit: Option<(Entity, &Note, usize)> = None;
+    for (entity, note) in note_query {
+        // Presses hit taps and the heads of holds and rolls, releases hit lifts, mines and fakes can't be hit
+        let on_release = note.kind == NoteKind::Lift;
+        if note.lane != input.lane || !note.kind.is_judged() || on_release == input.pressed || judged.contains(&entity) {
+            continue;
+        }
+        let Some(tier) = windows.judge(input.time - note.hit_time) else {
+            continue;
+        };
+        if best_hit.is_none_or(|(_, best, _)| note.hit_time < best.hit_time) {
+            best_hit = Some((entity, note, tier));
         }
     }
     
     // Process the best hit if any
-    if let Some((entity, overlap_percentage)) = best_hit {
-        // Remove the note
-        commands.entity(entity).despawn();
-        
-        // Determine score based on overlap
-        let (score_text, color, points, is_good_or_better) = if overlap_percentage >= 70.0 {
-            ("EXCELLENT!", Color::srgb(1.0, 1.0, 0.0), 100, true) // Yellow
-        } else if overlap_percentage >= 50.0 {
-            ("GREAT!", Color::srgb(0.0, 1.0, 0.0), 75, true) // Green
-        } else if overlap_percentage >= 30.0 {
-            ("GOOD", Color::srgb(0.0, 0.8, 1.0), 50, true) // Light Blue
+    if let Some((entity, note, tier)) = best_hit {
+        let judgement = &windows.tiers[tier];
+        judged.insert(entity);
+        // Taps are done, holds and rolls stay on the target until they're let go
+        if note.end_time.is_some() {
+            commands.entity(entity).insert(Holding { last_press: input.time });
         } else {
-            ("OK", Color::srgb(0.8, 0.8, 0.8), 25, false) // Gray
-        };
+            commands.entity(entity).despawn();
+        }
         
-        // Update score and streak
-        if is_good_or_better {
-            game_score.score += points;
-            game_score.streak += 1;
-        } else {
-            game_score.score += points;
-            game_score.streak = 0; // Reset streak for "OK" hits
+        // Keysounded charts only make their sound when the note is actually hit
+        if let Some(sample) = note.keysound.and_then(|index| keysounds.samples.get(index)) {
+            audio.play(sample.clone());
         }
         
-        // Spawn score text in the center of the screen
-        commands.spawn((
-            TextBundle::from_section(
-                score_text,
-                TextStyle {
-                    font_size: 48.0, // Bigger font for center display
-                    color,
-                    ..default()
-                },
-            )
-            .with_style(Style {
-                position_type: PositionType::Absolute,
-                left: Val::Px(640.0 - 100.0), // Center horizontally (screen width/2 - text width/2)
-                top: Val::Px(300.0), // Center vertically 
-                ..default()
-            }),
-            ScoreText {
-                timer: Timer::new(Duration::from_secs_f32(1.0), TimerMode::Once),
-            },
-        ));
+        // Negative offsets are early presses, positive ones late
+        let offset = input.time - note.hit_time;
+        hit_log.hits.push(HitRecord { lane: note.lane, hit_time: note.hit_time, offset, tier });
+        debug!("{} in lane {}: {:+.0}ms {}", 
+               judgement.name, note.lane, offset * 1000.0, 
+               if offset > 0.0 { "(LATE)" } else { "(EARLY)" });
+        
+        game_score.record_hit(tier, windows);
+        health.hit(judgement.breaks_combo);
+        
+        spawn_judgement_text(commands, judgement.name.clone(), judgement.color());
     }
-    
-    // Create visual metronome indicator (top center of screen)
+}
+
+// Spawn a judgement popup in the center of the screen
+fn spawn_judgement_text(commands: &mut Commands, text: String, color: Color) {
     commands.spawn((
-        SpriteBundle {
-            sprite: Sprite {
-                color: Color::NONE, // Start transparent
-                custom_size: Some(Vec2::new(20.0, 20.0)),
+        TextBundle::from_section(
+            text,
+            TextStyle {
+                font_size: 48.0, // Bigger font for center display
+                color,
                 ..default()
             },
-            transform: Transform::from_xyz(0.0, WINDOW_HEIGHT/2.0 - 50.0, 1.0),
+        )
+        .with_style(Style {
+            position_type: PositionType::Absolute,
+            left: Val::Px(640.0 - 100.0), // Center horizontally (screen width/2 - text width/2)
+            top: Val::Px(300.0), // Center vertically 
             ..default()
+        }),
+        ScoreText {
+            timer: Timer::new(Duration::from_secs_f32(1.0), TimerMode::Once),
         },
-        MetronomeFlash,
     ));
 }
 
+// Score a held note once it's let go, or when its tail reaches the target while still held
+pub fn handle_hold_notes(
+    mut commands: Commands,
+    lanes: Res<LaneState>,
+    clock: Res<SongClock>,
+    windows: Res<JudgementWindows>,
+    mut game_score: ResMut<GameScore>,
+    mut health: ResMut<Health>,
+    hold_query: Query<(Entity, &Note, &Holding, Option<&Children>)>,
+    mut sprite_query: Query<&mut Sprite>,
+) {
+    let roll_window = windows.roll_window_ms / 1000.0;
+    for (entity, note, holding, children) in &hold_query {
+        let Some(end_time) = note.end_time else {
+            continue;
+        };
+        // Holds need the lane kept down, rolls a fresh tap every so often
+        let is_roll = note.kind == NoteKind::Roll;
+        let still_going = if is_roll { clock.now <= holding.last_press + roll_window } else { lanes.held[note.lane] };
+        if still_going && clock.now < end_time {
+            continue;
+        }
+        
+        // A roll was let go when its last tap ran out, holding all the way through counts as letting go right on the tail
+        let let_go_time = if is_roll { clock.now.min(holding.last_press + roll_window) } else { clock.now };
+        let offset = (let_go_time - end_time).min(0.0);
+        let what = if is_roll { "roll" } else { "hold" };
+        match windows.judge_release(offset) {
+            Some(tier) => {
+                let judgement = &windows.tiers[tier];
+                commands.entity(entity).despawn_recursive();
+                debug!("{} {} release in lane {}: {:+.0}ms", judgement.name, what, note.lane, offset * 1000.0);
+                
+                game_score.record_hit(tier, &windows);
+                health.hit(judgement.breaks_combo);
+                spawn_judgement_text(&mut commands, judgement.name.clone(), judgement.color());
+            }
+            None => {
+                // Let go too early, the rest of the hold is lost
+                commands.entity(entity).remove::<Holding>().insert(HoldDropped { time: let_go_time });
+                dim_note(entity, children, &mut sprite_query);
+                game_score.record_drop(&windows);
+                health.miss();
+                debug!("Dropped {} in lane {}, let go {:.0}ms early", what, note.lane, -offset * 1000.0);
+                spawn_judgement_text(&mut commands, "DROPPED".to_string(), Color::srgb(1.0, 0.3, 0.3));
+            }
+        }
+    }
+}
+
+// Grey out a missed note or a dropped hold's head and body while what's left of it scrolls away
+fn dim_note(entity: Entity, children: Option<&Children>, sprite_query: &mut Query<&mut Sprite>) {
+    for id in std::iter::once(entity).chain(children.into_iter().flatten().copied()) {
+        if let Ok(mut sprite) = sprite_query.get_mut(id) {
+            sprite.color = Color::srgba(0.4, 0.4, 0.4, 0.6);
+        }
+    }
+}
+
+// Request the picked chart from the AssetServer, `apply_chart_changes` starts it once it's in
+pub fn load_chart(mut commands: Commands, asset_server: Res<AssetServer>, selected: Res<SelectedChart>) {
+    info!("Loading chart {}", selected.path);
+    commands.insert_resource(CurrentChart {
+        handle: asset_server.load(selected.path.clone()),
+        pending: true,
+    });
+}
+
+// Startup system that loads the bundled metronome and target cue samples
+pub fn load_cue_sounds(
+    asset_server: Res<AssetServer>,
+    mut metronome: ResMut<Metronome>,
+    mut cue: ResMut<TargetCue>,
+) {
+    metronome.audio_handle = Some(asset_server.load(METRONOME_CLICK_PATH));
+    metronome.accent_handle = Some(asset_server.load(METRONOME_ACCENT_PATH));
+    cue.audio_handle = Some(asset_server.load(TARGET_CUE_PATH));
+}
+
+// M toggles the metronome, N the target cue
+pub fn toggle_audio_cues(
+    keys: Res<ButtonInput<KeyCode>>,
+    mut metronome: ResMut<Metronome>,
+    mut cue: ResMut<TargetCue>,
+    tempo_map: Res<TempoMap>,
+    timer: Res<NoteSpawnTimer>,
+    clock: Res<SongClock>,
+) {
+    let on_off = |active: bool| if active { "on" } else { "off" };
+    
+    if keys.just_pressed(KeyCode::KeyM) {
+        metronome.is_active = !metronome.is_active;
+        // Pick the clicks back up from now rather than catching up on the ones we skipped
+        let current_beat = tempo_map.seconds_to_beat(clock.now - timer.song_start_time);
+        metronome.next_click = tempo_map.next_click(Beat::from_f32(current_beat));
+        info!("Metronome {}", on_off(metronome.is_active));
+    }
+    if keys.just_pressed(KeyCode::KeyN) {
+        cue.is_active = !cue.is_active;
+        info!("Target cue {}", on_off(cue.is_active));
+    }
+}
+
+// Push cue volume changes to their audio channels
+pub fn apply_cue_volumes(
+    metronome: Res<Metronome>,
+    cue: Res<TargetCue>,
+    metronome_channel: Res<AudioChannel<MetronomeChannel>>,
+    cue_channel: Res<AudioChannel<TargetCueChannel>>,
+    mut applied: Local<Option<(f64, f64)>>,
+) {
+    // The metronome resource changes on every click, so compare against what was last applied
+    let volumes = (metronome.volume, cue.volume);
+    if *applied != Some(volumes) {
+        metronome_channel.set_volume(volumes.0);
+        cue_channel.set_volume(volumes.1);
+        *applied = Some(volumes);
+    }
+}
+
+// Swap in the chart once it loads, and restart the song whenever the file is edited on disk
+pub fn apply_chart_changes(
+    mut commands: Commands,
+    mut chart_events: EventReader<AssetEvent<Chart>>,
+    mut failed_events: EventReader<AssetLoadFailedEvent<Chart>>,
+    charts: Res<Assets<Chart>>,
+    current_chart: Option<ResMut<CurrentChart>>,
+    mut timer: ResMut<NoteSpawnTimer>,
+    mut tempo_map: ResMut<TempoMap>,
+    mut metronome: ResMut<Metronome>,
+    mut game_score: ResMut<GameScore>,
+    mut hit_log: ResMut<HitLog>,
+    mut health: ResMut<Health>,
+    mut keysounds: ResMut<KeysoundBank>,
+    mut clock: ResMut<SongClock>,
+    asset_server: Res<AssetServer>,
+    note_query: Query<Entity, With<Note>>,
+) {
+    for failed in failed_events.read() {
+        error!("Could not load chart {}: {}", failed.path, failed.error);
+    }
+    
+    let Some(mut current_chart) = current_chart else {
+        return;
+    };
+    
+    // A reload can report both events in the same frame, only restart once
+    let mut changed = current_chart.pending;
+    for event in chart_events.read() {
+        if let AssetEvent::LoadedWithDependencies { id } | AssetEvent::Modified { id } = event {
+            changed |= *id == current_chart.handle.id();
+        }
+    }
+    let Some(chart) = charts.get(&current_chart.handle).filter(|_| changed) else {
+        return;
+    };
+    current_chart.pending = false;
+    
+    // Clear out the previous version's notes and start over from the top
+    for entity in &note_query {
+        commands.entity(entity).despawn_recursive();
+    }
+    let song_start_time = clock.now + SONG_LEAD_IN;
+    *tempo_map = chart.tempo_map();
+    timer.song_start_time = song_start_time;
+    timer.load_chart(chart, &tempo_map);
+    metronome.song_start_time = song_start_time;
+    metronome.next_click = Beat::ZERO;
+    *game_score = GameScore::for_chart(chart);
+    *hit_log = HitLog::default();
+    health.reset();
+    
+    // Song and keysound paths are relative to the chart file
+    let chart_dir = asset_server
+        .get_path(current_chart.handle.id())
+        .and_then(|path| path.path().parent().map(|dir| dir.to_path_buf()))
+        .unwrap_or_default();
+    *keysounds = KeysoundBank {
+        samples: chart.keysounds.iter().map(|sample| asset_server.load(chart_dir.join(sample))).collect(),
+        background: chart.background_sounds.clone(),
+        background_index: 0,
+        song_start_time,
+    };
+    // The clock sees the new start time and plays the song from the top once it's reached
+    clock.audio = chart.audio.as_ref().map(|song| asset_server.load(chart_dir.join(song)));
+    
+    info!("Loaded chart \"{}\" ({} notes at {} BPM), song starts at {:.3}s",
+             chart.metadata.title, chart.notes.len(), chart.bpm, song_start_time);
+}
+
+// Advance the gameplay clock, starting the song when its start time comes up and following its playback position
+pub fn update_song_clock(
+    time: Res<Time>,
+    timer: Res<NoteSpawnTimer>,
+    mut clock: ResMut<SongClock>,
+    audio: Res<Audio>,
+    audio_sources: Res<Assets<AudioSource>>,
+    mut audio_instances: ResMut<Assets<AudioInstance>>,
+) {
+    clock.now += time.delta_seconds();
+    
+    // The pattern restarted or a new chart loaded, stop the song so it plays again from the top
+    if clock.song_start_time != timer.song_start_time {
+        clock.song_start_time = timer.song_start_time;
+        clock.last_position = None;
+        if let Some(instance) = clock.instance.take().and_then(|handle| audio_instances.get_mut(&handle)) {
+            instance.stop(AudioTween::default());
+        }
+    }
+    
+    match clock.instance.as_ref() {
+        Some(handle) => {
+            // Kira reports the position once per audio block, so only snap to it when it moves
+            let position = audio_instances.get(handle).and_then(|instance| instance.state().position());
+            if let Some(position) = position.filter(|position| Some(*position) != clock.last_position) {
+                clock.last_position = Some(position);
+                clock.now = clock.song_start_time + position as f32;
+            }
+        }
+        None => {
+            let Some(song) = clock.audio.clone() else {
+                return;
+            };
+            // A song that finished loading late joins in at the current position
+            let position = clock.now - clock.song_start_time;
+            if position >= 0.0 && audio_sources.contains(&song) {
+                clock.instance = Some(audio.play(song).start_from(position as f64).handle());
+                info!("Song started at {:.3}s ({:.3}s in)", clock.now, position);
+            }
+        }
+    }
+}
+
 // Note spawning system
 pub fn spawn_notes(
     mut commands: Commands,
     time: Res<Time>,
+    clock: Res<SongClock>,
     mut timer: ResMut<NoteSpawnTimer>,
+    tempo_map: Res<TempoMap>,
 ) {
     timer.timer.tick(time.delta());
     
     if timer.timer.just_finished() {
-        let current_time = time.elapsed_seconds();
-        
-        // Calculate when we should spawn the next note
-        // Spawn time = when note should hit target - travel time
-        let target_hit_time = timer.next_beat_time;
-        let spawn_time = target_hit_time - TRAVEL_TIME;
+        let current_time = clock.now;
         
-        // Check if it's time to spawn the next note (with small tolerance to prevent frame timing issues)
-        if current_time >= (spawn_time - 0.05) && timer.pattern_index < timer.current_pattern.len() {
-            let (note_duration, lane) = timer.current_pattern[timer.pattern_index];
+        // Spawn every note that is due, so chords and fast streams aren't held back by the tick rate
+        while timer.pattern_index < timer.current_pattern.len() {
+            let note = timer.current_pattern[timer.pattern_index];
             
-            let x_pos = LANES[lane];
-            let note_color = note_duration.color();
+            // Spawn time = when note should hit target - travel time
+            let target_hit_time = timer.hit_time(&tempo_map, note.beat);
+            let end_time = note.end_beat.map(|end| timer.hit_time(&tempo_map, end));
+            let spawn_time = target_hit_time - TRAVEL_TIME;
             
-            // Spawn at consistent height - timing is handled by spawn timing, not position
-            let spawn_y = SPAWN_Y;
+            // Small tolerance to prevent frame timing issues
+            if current_time < spawn_time - 0.05 {
+                timer.next_beat_time = target_hit_time;
+                break;
+            }
             
-            // Spawn the note
-            commands.spawn((
-                SpriteBundle {
-                    sprite: Sprite {
-                        color: note_color,
-                        custom_size: Some(Vec2::new(NOTE_SIZE, NOTE_SIZE)),
-                        ..default()
-                    },
-                    transform: Transform::from_xyz(x_pos, spawn_y, 0.0),
-                    ..default()
-                },
-                Note { lane },
-            ));
+            let note_duration = NoteDuration::from_beat(note.beat);
+            let x_pos = LANES[note.lane];
+            let transform = Transform::from_xyz(x_pos, note_y(target_hit_time, current_time), 0.0);
             
-        // Calculate the next beat time based on the note duration
-        // Use precise timing to prevent accumulating drift while preserving subdivisions
-        let next_beat_offset = note_duration.to_seconds();
-        timer.next_beat_time += next_beat_offset;
-        
-        // Apply drift correction only for main beats (whole note intervals)
-        // This preserves subdivisions while preventing long-term drift
-        if (next_beat_offset - BEAT_INTERVAL).abs() < 0.001 { // If this is a main beat
-            let beats_from_start = (timer.next_beat_time - timer.song_start_time) / BEAT_INTERVAL;
-            let corrected_beats = beats_from_start.round();
-            timer.next_beat_time = timer.song_start_time + corrected_beats * BEAT_INTERVAL;
-        }
-        timer.pattern_index += 1;            // Debug info with detailed timing comparison
-            let beat_number = (target_hit_time - timer.song_start_time) / BEAT_INTERVAL + 1.0;
-            let expected_metronome_time = timer.song_start_time + (beat_number - 1.0) * BEAT_INTERVAL;
-            let actual_spawn_delay = current_time - spawn_time;
+            // Lifts are flat bars, mines small dark diamonds and fakes faded copies of a normal note
+            let (sprite, transform, name) = match note.kind {
+                NoteKind::Tap | NoteKind::Roll => (
+                    Sprite { color: note_duration.color(), custom_size: Some(Vec2::new(NOTE_SIZE, NOTE_SIZE)), ..default() },
+                    transform,
+                    if note.kind == NoteKind::Roll { "roll" } else { note_duration.name() },
+                ),
+                NoteKind::Lift => (
+                    Sprite { color: note_duration.color(), custom_size: Some(Vec2::new(NOTE_SIZE, LIFT_HEIGHT)), ..default() },
+                    transform,
+                    "lift",
+                ),
+                NoteKind::Mine => (
+                    Sprite { color: Color::srgb(0.5, 0.05, 0.1), custom_size: Some(Vec2::new(MINE_SIZE, MINE_SIZE)), ..default() },
+                    transform.with_rotation(Quat::from_rotation_z(std::f32::consts::FRAC_PI_4)),
+                    "mine",
+                ),
+                NoteKind::Fake => (
+                    Sprite { color: note_duration.color().with_alpha(0.3), custom_size: Some(Vec2::new(NOTE_SIZE, NOTE_SIZE)), ..default() },
+                    transform,
+                    "fake",
+                ),
+            };
             
-            println!("🎵 Spawned {} note in lane {} at time {:.3}s", 
-                     note_duration.name(), lane, current_time);
-            println!("   → Should hit target at {:.3}s (Beat {:.1})", 
-                     target_hit_time, beat_number);
-            println!("   → Expected metronome beat at {:.3}s", 
-                     expected_metronome_time);
-            println!("   → Travel time: {:.3}s, Spawn delay: {:.3}s", 
-                     TRAVEL_TIME, actual_spawn_delay);
-                     
-        } else if timer.pattern_index >= timer.current_pattern.len() {
-            // Pattern finished, restart from beginning
-            timer.pattern_index = 0;
-            timer.next_beat_time = current_time + 2.0; // 2 second pause before restarting
-            println!("Pattern completed, restarting in 2 seconds...");
+            // Spawn where the note would be now, `move_notes` keeps it on time from here
+            let mut head = commands.spawn((
+                SpriteBundle { sprite, transform, ..default() },
+                Note { lane: note.lane, kind: note.kind, keysound: note.keysound, hit_time: target_hit_time, end_time },
+            ));
+            // Hold notes trail a body up to their tail, rolls an orange one
+            if let Some(end_time) = end_time {
+                let length = (end_time - target_hit_time) * NOTE_SPEED;
+                let body_color = if note.kind == NoteKind::Roll { Color::srgba(1.0, 0.55, 0.1, 0.7) } else { note_duration.color().with_alpha(0.6) };
+                head.with_children(|parent| {
+                    parent.spawn((
+                        SpriteBundle {
+                            sprite: Sprite {
+                                color: body_color,
+                                custom_size: Some(Vec2::new(HOLD_BODY_WIDTH, length)),
+                                ..default()
+                            },
+                            transform: Transform::from_xyz(0.0, length / 2.0, -0.1),
+                            ..default()
+                        },
+                        HoldBody,
+                    ));
+                });
+            }
+            timer.pattern_index += 1;
+            
+            debug!("Spawned {} note in lane {} at {:.3}s ({:.3}s late), hits the target at {:.3}s ({})", 
+                   name, note.lane, current_time, current_time - spawn_time,
+                   target_hit_time, beat_label(&tempo_map, note.beat));
+        }
+
+    }
+}
+
+// Play the chart's background keysounds as the song reaches them
+pub fn play_background_sounds(
+    clock: Res<SongClock>,
+    timer: Res<NoteSpawnTimer>,
+    tempo_map: Res<TempoMap>,
+    mut keysounds: ResMut<KeysoundBank>,
+    audio: Res<Audio>,
+) {
+    // The pattern restarted (or a new chart loaded), start over from the first sound
+    if keysounds.song_start_time != timer.song_start_time {
+        keysounds.song_start_time = timer.song_start_time;
+        keysounds.background_index = 0;
+    }
+    
+    let current_time = clock.now;
+    while let Some(sound) = keysounds.background.get(keysounds.background_index).copied() {
+        if timer.hit_time(&tempo_map, sound.beat) > current_time {
+            break;
+        }
+        if let Some(sample) = keysounds.samples.get(sound.keysound) {
+            audio.play(sample.clone());
         }
+        keysounds.background_index += 1;
     }
 }
 
-// Note movement system
+// Note movement system - notes are placed from the song clock, so a hitch can't knock them off the beat
 pub fn move_notes(
-    mut note_query: Query<&mut Transform, With<Note>>,
-    time: Res<Time>,
+    mut note_query: Query<(&mut Transform, &Note, Option<&Children>, Has<Holding>, Option<&HoldDropped>)>,
+    mut body_query: Query<(&mut Transform, &mut Sprite), (With<HoldBody>, Without<Note>)>,
+    clock: Res<SongClock>,
 ) {
-    for mut transform in &mut note_query {
-        // Move notes downward
-        transform.translation.y -= NOTE_SPEED * time.delta_seconds();
+    for (mut transform, note, children, holding, dropped) in &mut note_query {
+        // A held note's head waits on the target while its body drains into it
+        let head_time = match dropped {
+            _ if holding => note.hit_time.max(clock.now),
+            Some(dropped) => note.hit_time.max(dropped.time),
+            None => note.hit_time,
+        };
+        transform.translation.y = note_y(head_time, clock.now);
+        
+        let (Some(end_time), Some(children)) = (note.end_time, children) else {
+            continue;
+        };
+        let length = ((end_time - head_time) * NOTE_SPEED).max(0.0);
+        for child in children {
+            if let Ok((mut body_transform, mut sprite)) = body_query.get_mut(*child) {
+                body_transform.translation.y = length / 2.0;
+                sprite.custom_size = Some(Vec2::new(HOLD_BODY_WIDTH, length));
+            }
+        }
     }
 }
 
+// Height of a note that hits the target at `hit_time`, falling at NOTE_SPEED
+fn note_y(hit_time: f32, now: f32) -> f32 {
+    TARGET_Y + (hit_time - now) * NOTE_SPEED
+}
+
 // System to detect when notes reach the target area and play a "should hit" sound
 pub fn note_target_detection(
     mut commands: Commands,
-    note_query: Query<(Entity, &Transform), (With<Note>, Without<NoteTargetTriggered>)>,
-    time: Res<Time>,
+    note_query: Query<(Entity, &Transform, &Note), Without<NoteTargetTriggered>>,
+    cue: Res<TargetCue>,
+    cue_channel: Res<AudioChannel<TargetCueChannel>>,
 ) {
-    for (entity, transform) in note_query.iter() {
+    for (entity, transform, note) in note_query.iter() {
+        if !note.kind.is_judged() {
+            continue;
+        }
         let note_y = transform.translation.y;
         let target_y = TARGET_Y;
         
         // Check if note has crossed the target line (going downward)
         if note_y <= target_y + 10.0 && note_y >= target_y - 10.0 {
             // Play a different sound when notes reach the target area
-            std::process::Command::new("afplay")
-                .arg("/System/Library/Sounds/Pop.aiff") // Different sound than metronome
-                .spawn()
-                .ok();
-            
-            let current_time = time.elapsed_seconds();
-            
-            // Calculate which beat this note was supposed to hit on
-            let expected_hit_time = ((current_time - 1.0) / BEAT_INTERVAL).round() * BEAT_INTERVAL + 1.0;
-            let timing_error = current_time - expected_hit_time;
-            
-            println!("🎯 NOTE HIT TARGET at {:.3}s (note y: {:.1}, target y: {:.1})", 
-                     current_time, note_y, target_y);
-            println!("   → Expected to hit at {:.3}s", expected_hit_time);
-            println!("   → Timing error: {:.3}s {}", 
-                     timing_error.abs(), 
-                     if timing_error > 0.0 { "(LATE)" } else { "(EARLY)" });
+            if let Some(pop) = cue.audio_handle.clone().filter(|_| cue.is_active) {
+                cue_channel.play(pop);
+            }
             
             // Mark this note as having triggered to prevent duplicate sounds
             commands.entity(entity).insert(NoteTargetTriggered);
@@ -431,42 +839,182 @@ pub fn note_target_detection(
 // Clean up notes that have fallen off screen
 pub fn cleanup_notes(
     mut commands: Commands,
-    note_query: Query<(Entity, &Transform), With<Note>>,
+    note_query: Query<(Entity, &Note, Has<Missed>, Has<HoldDropped>)>,
+    clock: Res<SongClock>,
 ) {
-    for (entity, transform) in &note_query {
-        // Remove notes that have moved past the bottom of the screen
-        if transform.translation.y < -400.0 {
-            commands.entity(entity).despawn();
+    for (entity, note, missed, dropped) in &note_query {
+        // Notes still waiting on a judgement are left for `handle_missed_notes`
+        if note.kind.is_judged() && !missed && !dropped {
+            continue;
+        }
+        // Remove notes once they (and a hold's whole body) have moved past the bottom of the screen
+        if note_y(note.end_time.unwrap_or(note.hit_time), clock.now) < -400.0 {
+            commands.entity(entity).despawn_recursive();
         }
     }
 }
 
-// Handle notes that were missed
+// Notes that are still unhit once their late window has passed are misses
 pub fn handle_missed_notes(
     mut commands: Commands,
-    note_query: Query<(Entity, &Transform), With<Note>>,
+    note_query: Query<(Entity, &Note, Option<&Children>), (Without<Holding>, Without<HoldDropped>, Without<Missed>)>,
+    mut sprite_query: Query<&mut Sprite>,
+    clock: Res<SongClock>,
+    windows: Res<JudgementWindows>,
+    mut game_score: ResMut<GameScore>,
+    mut hit_log: ResMut<HitLog>,
+    mut health: ResMut<Health>,
+) {
+    let late_window = windows.widest();
+    for (entity, note, children) in &note_query {
+        // Mines are meant to be let through and fakes don't count, they just scroll off
+        if !note.kind.is_judged() || clock.now <= note.hit_time + late_window {
+            continue;
+        }
+        
+        game_score.record_miss(&windows);
+        hit_log.misses.push(note.hit_time);
+        health.miss();
+        
+        // Missed notes keep scrolling, greyed out, so it's clear what was missed
+        commands.entity(entity).insert(Missed);
+        if note.end_time.is_some() {
+            // Holds count their head and their tail, a missed head loses the tail too
+            game_score.record_drop(&windows);
+            commands.entity(entity).insert(HoldDropped { time: note.hit_time });
+        }
+        dim_note(entity, children, &mut sprite_query);
+        
+        debug!("Miss in lane {} (due at {:.3}s), health {:.0}%", note.lane, note.hit_time, health.value * 100.0);
+        spawn_judgement_text(&mut commands, "MISS".to_string(), Color::srgb(1.0, 0.1, 0.1));
+    }
+}
+
+// Set off mines whose lane is held down as they cross the target, the rest pass by harmlessly
+pub fn handle_mines(
+    mut commands: Commands,
+    lanes: Res<LaneState>,
+    clock: Res<SongClock>,
+    note_query: Query<(Entity, &Note)>,
     mut game_score: ResMut<GameScore>,
+    mut health: ResMut<Health>,
+    mut last_time: Local<Option<f32>>,
 ) {
-    let hit_zone_height = 100.0;
-    for (entity, transform) in &note_query {
-        // Check if note passed the hit zone without being hit
-        if transform.translation.y < TARGET_Y - hit_zone_height {
-            // Reset streak for missed notes
-            game_score.streak = 0;
+    // Check every mine that crossed since the last frame, so a hitch can't let one slip through
+    let since = last_time.replace(clock.now).unwrap_or(clock.now);
+    for (entity, note) in &note_query {
+        if note.kind != NoteKind::Mine || note.hit_time <= since || note.hit_time > clock.now {
+            continue;
+        }
+        if lanes.held[note.lane] {
             commands.entity(entity).despawn();
+            game_score.record_mine();
+            health.mine();
+            debug!("Mine went off in lane {} (-{} points)", note.lane, MINE_PENALTY);
+            spawn_judgement_text(&mut commands, "MINE!".to_string(), Color::srgb(1.0, 0.2, 0.1));
+        }
+    }
+}
+
+// Gameplay that should stop once the run has failed
+pub fn still_playing(health: Res<Health>) -> bool {
+    !health.failed
+}
+
+// Stretch the life bar to the health left, going from green to red as it empties
+pub fn update_life_bar(
+    health: Res<Health>,
+    mut fill_query: Query<&mut Sprite, With<LifeBarFill>>,
+) {
+    for mut sprite in &mut fill_query {
+        sprite.custom_size = Some(Vec2::new(LIFE_BAR_WIDTH, LIFE_BAR_HEIGHT * health.value));
+        sprite.color = if health.value > 0.5 {
+            Color::srgb(0.2, 1.0, 0.2) // Green
+        } else if health.value > 0.25 {
+            Color::srgb(1.0, 1.0, 0.2) // Yellow
+        } else {
+            Color::srgb(1.0, 0.2, 0.2) // Red
+        };
+    }
+}
+
+// F toggles no-fail, so an empty life gauge doesn't end the run
+pub fn toggle_no_fail(keys: Res<ButtonInput<KeyCode>>, mut health: ResMut<Health>) {
+    if keys.just_pressed(KeyCode::KeyF) {
+        health.no_fail = !health.no_fail;
+        info!("No-fail {}", if health.no_fail { "on" } else { "off" });
+    }
+}
+
+// Once the life gauge runs out: stop the song, clear the notes and show that the run failed
+pub fn handle_failure(
+    mut commands: Commands,
+    health: Res<Health>,
+    mut clock: ResMut<SongClock>,
+    mut audio_instances: ResMut<Assets<AudioInstance>>,
+    note_query: Query<Entity, With<Note>>,
+    failed_query: Query<Entity, With<FailedUI>>,
+    mut shown: Local<bool>,
+) {
+    if !health.failed {
+        // A new run started (the chart was reloaded), take the failed screen down
+        if *shown {
+            for entity in &failed_query {
+                commands.entity(entity).despawn_recursive();
+            }
+            *shown = false;
         }
+        return;
     }
+    if *shown {
+        return;
+    }
+    *shown = true;
+    
+    if let Some(instance) = clock.instance.take().and_then(|handle| audio_instances.get_mut(&handle)) {
+        instance.stop(AudioTween::default());
+    }
+    clock.audio = None; // Keeps `update_song_clock` from starting the song again
+    for entity in &note_query {
+        commands.entity(entity).despawn_recursive();
+    }
+    
+    commands.spawn((
+        TextBundle::from_section(
+            "FAILED",
+            TextStyle {
+                font_size: 96.0,
+                color: Color::srgb(1.0, 0.1, 0.1),
+                ..default()
+            },
+        )
+        .with_style(Style {
+            position_type: PositionType::Absolute,
+            top: Val::Px(200.0),
+            width: Val::Percent(100.0),
+            justify_content: JustifyContent::Center,
+            ..default()
+        })
+        .with_text_justify(JustifyText::Center),
+        FailedUI,
+    ));
+    info!("Failed at {:.3}s", clock.now);
 }
 
 // Update the UI
 pub fn update_ui(
     game_score: Res<GameScore>,
-    mut score_query: Query<&mut Text, (With<ScoreUI>, Without<StreakUI>)>,
-    mut streak_query: Query<&mut Text, (With<StreakUI>, Without<ScoreUI>)>,
+    windows: Res<JudgementWindows>,
+    settings: Res<Settings>,
+    mut score_query: Query<&mut Text, (With<ScoreUI>, Without<StreakUI>, Without<JudgementCountsUI>)>,
+    mut streak_query: Query<&mut Text, (With<StreakUI>, Without<ScoreUI>, Without<JudgementCountsUI>)>,
+    mut counts_query: Query<&mut Text, (With<JudgementCountsUI>, Without<ScoreUI>, Without<StreakUI>)>,
 ) {
     // Update score display
     if let Ok(mut score_text) = score_query.get_single_mut() {
-        score_text.sections[0].value = format!("Score: {}", game_score.score);
+        if let Some(system) = game_score.system(settings.scoring) {
+            score_text.sections[0].value = format!("{}: {}", system.name(), system.score());
+        }
     }
     
     // Update streak display with dynamic color
@@ -484,6 +1032,24 @@ pub fn update_ui(
             Color::srgb(1.0, 0.8, 0.2) // Golden default
         };
     }
+    
+    // One line per tier, then misses, combo and the accuracy colored by grade
+    if let Ok(mut counts_text) = counts_query.get_single_mut() {
+        let mut counts = String::new();
+        for (tier, judgement) in windows.tiers.iter().enumerate() {
+            counts.push_str(&format!("{}: {}\n", judgement.name, game_score.counts.get(tier).copied().unwrap_or(0)));
+        }
+        counts.push_str(&format!("MISS: {}\n", game_score.misses));
+        if game_score.drops > 0 {
+            counts.push_str(&format!("DROPPED: {}\n", game_score.drops));
+        }
+        counts.push_str(&format!("Max combo: {}\nNotes: {}\n", game_score.max_combo, game_score.total_notes));
+        counts_text.sections[0].value = counts;
+        
+        let grade = game_score.grade();
+        counts_text.sections[1].value = format!("{:.2}% {}", game_score.accuracy(), grade.name());
+        counts_text.sections[1].style.color = grade.color();
+    }
 }
 
 // Clean up score text after timeout
@@ -595,64 +1161,78 @@ pub fn animate_button_press(
     }
 }
 
-// Create the demo pattern
-pub fn create_demo_pattern() -> Vec<(NoteDuration, usize)> {
-    // Pattern with 4 of each note type for comprehensive timing test
-    vec![
-        // 4 Whole notes (4 seconds each at 60 BPM) - RED
-        (NoteDuration::Whole, 0), // Hits at 2s
-        (NoteDuration::Whole, 1), // Hits at 6s  
-        (NoteDuration::Whole, 2), // Hits at 10s
-        (NoteDuration::Whole, 3), // Hits at 14s
-        
-        // 4 Half notes (2 seconds each at 60 BPM) - RED
-        (NoteDuration::Half, 0),  // Hits at 18s
-        (NoteDuration::Half, 1),  // Hits at 20s
-        (NoteDuration::Half, 2),  // Hits at 22s
-        (NoteDuration::Half, 3),  // Hits at 24s
-        
-        // 4 Quarter notes (1 second each at 60 BPM) - RED
-        (NoteDuration::Quarter, 0), // Hits at 26s
-        (NoteDuration::Quarter, 1), // Hits at 27s
-        (NoteDuration::Quarter, 2), // Hits at 28s
-        (NoteDuration::Quarter, 3), // Hits at 29s
-        
-        // 4 Eighth notes (0.5 seconds each at 60 BPM) - BLUE
-        (NoteDuration::Eighth, 0),  // Hits at 30s
-        (NoteDuration::Eighth, 1),  // Hits at 30.5s
-        (NoteDuration::Eighth, 2),  // Hits at 31s
-        (NoteDuration::Eighth, 3),  // Hits at 31.5s
-        
-        // 4 Sixteenth notes (0.25 seconds each at 60 BPM) - GREEN
-        (NoteDuration::Sixteenth, 0), // Hits at 32s
-        (NoteDuration::Sixteenth, 1), // Hits at 32.25s
-        (NoteDuration::Sixteenth, 2), // Hits at 32.5s
-        (NoteDuration::Sixteenth, 3), // Hits at 32.75s
-        
-        // 16 Fast Notes - 4 beats worth (4 notes per beat × 4 beats = 16 notes) - GREEN (using sixteenth timing)
-        (NoteDuration::Sixteenth, 0),   // Beat 1.0: Hits at 33s
-        (NoteDuration::Sixteenth, 1),   // Beat 1.25: Hits at 33.25s
-        (NoteDuration::Sixteenth, 2),   // Beat 1.5: Hits at 33.5s
-        (NoteDuration::Sixteenth, 3),   // Beat 1.75: Hits at 33.75s
-        (NoteDuration::Sixteenth, 0),   // Beat 2.0: Hits at 34s
-        (NoteDuration::Sixteenth, 1),   // Beat 2.25: Hits at 34.25s
-        (NoteDuration::Sixteenth, 2),   // Beat 2.5: Hits at 34.5s
-        (NoteDuration::Sixteenth, 3),   // Beat 2.75: Hits at 34.75s
-        (NoteDuration::Sixteenth, 0),   // Beat 3.0: Hits at 35s
-        (NoteDuration::Sixteenth, 1),   // Beat 3.25: Hits at 35.25s
-        (NoteDuration::Sixteenth, 2),   // Beat 3.5: Hits at 35.5s
-        (NoteDuration::Sixteenth, 3),   // Beat 3.75: Hits at 35.75s
-        (NoteDuration::Sixteenth, 0),   // Beat 4.0: Hits at 36s
-        (NoteDuration::Sixteenth, 1),   // Beat 4.25: Hits at 36.25s
-        (NoteDuration::Sixteenth, 2),   // Beat 4.5: Hits at 36.5s
-        (NoteDuration::Sixteenth, 3),   // Beat 4.75: Hits at 36.75s
-    ]
-}
-
-// Metronome system - plays a click on each beat
+// Hold the song where it is while the game is paused, the clock and everything driven by it stop with it
+pub fn pause_song(clock: Res<SongClock>, mut audio_instances: ResMut<Assets<AudioInstance>>) {
+    if let Some(instance) = clock.instance.as_ref().and_then(|handle| audio_instances.get_mut(handle)) {
+        instance.pause(AudioTween::default());
+    }
+}
+
+// Pick the song back up, letting go of any lane whose key came up while the menu was open
+pub fn resume_song(
+    clock: Res<SongClock>,
+    keys: Res<ButtonInput<KeyCode>>,
+    mut lanes: ResMut<LaneState>,
+    mut lane_inputs: EventWriter<LaneInput>,
+    mut audio_instances: ResMut<Assets<AudioInstance>>,
+) {
+    if let Some(instance) = clock.instance.as_ref().and_then(|handle| audio_instances.get_mut(handle)) {
+        instance.resume(AudioTween::default());
+    }
+    // `handle_input` doesn't run while paused, so those releases were never sent and the holds would stay held
+    for (lane, key) in LANE_KEYS.into_iter().enumerate() {
+        if lanes.held[lane] && !keys.pressed(key) {
+            lanes.held[lane] = false;
+            lane_inputs.send(LaneInput { lane, time: clock.now, pressed: false });
+        }
+    }
+    info!("Resumed at {:.3}s", clock.now);
+}
+
+// Head to the results once the last note is over, or a little after the run failed
+pub fn check_chart_end(
+    clock: Res<SongClock>,
+    timer: Res<NoteSpawnTimer>,
+    tempo_map: Res<TempoMap>,
+    health: Res<Health>,
+    mut next_state: ResMut<NextState<GameState>>,
+    mut failed_at: Local<Option<f32>>,
+) {
+    // The song was quit this frame, that wins over the results
+    if matches!(*next_state, NextState::Pending(_)) {
+        return;
+    }
+    let end_time = if health.failed {
+        *failed_at.get_or_insert(clock.now)
+    } else {
+        *failed_at = None;
+        if timer.pattern_index < timer.current_pattern.len() {
+            return;
+        }
+        let last_beat = timer.current_pattern.iter().map(|note| note.end_beat.unwrap_or(note.beat)).max();
+        timer.hit_time(&tempo_map, last_beat.unwrap_or(Beat::ZERO))
+    };
+    if clock.now >= end_time + RESULTS_DELAY {
+        next_state.set(GameState::Results);
+    }
+}
+
+// Startup system that finds the songs to pick from
+pub fn scan_song_library(mut commands: Commands) {
+    let mut library = SongLibrary::scan(ASSETS_DIR, SONGS_DIR);
+    // The demo chart is always there, so there's something to play without any songs
+    library.songs.extend(Song::load(Path::new(ASSETS_DIR), DEMO_SONG_DIR));
+    info!("Found {} songs", library.songs.len());
+    commands.insert_resource(library);
+}
+
+// Metronome system - plays a click on each beat of the chart's time signature, following its tempo map
 pub fn metronome_system(
     mut metronome: ResMut<Metronome>,
-    time: Res<Time>,
+    timer: Res<NoteSpawnTimer>,
+    tempo_map: Res<TempoMap>,
+    clock: Res<SongClock>,
+    metronome_channel: Res<AudioChannel<MetronomeChannel>>,
     mut commands: Commands,
     mut flash_query: Query<&mut Sprite, With<MetronomeFlash>>,
 ) {
@@ -660,15 +1240,32 @@ pub fn metronome_system(
         return;
     }
     
-    let current_time = time.elapsed_seconds();
+    let current_time = clock.now;
+    
+    // The pattern restarted, pick the clicks up from wherever the new start puts us
+    if metronome.song_start_time != timer.song_start_time {
+        metronome.song_start_time = timer.song_start_time;
+        let current_beat = tempo_map.seconds_to_beat(current_time - metronome.song_start_time);
+        metronome.next_click = tempo_map.next_click(Beat::whole(current_beat.floor() as i64 - 1));
+        while metronome.song_start_time + tempo_map.beat_to_seconds(metronome.next_click) < current_time {
+            metronome.next_click = tempo_map.next_click(metronome.next_click);
+        }
+    }
     
     // Check if it's time for the next beat
-    if current_time >= metronome.next_beat_time {
-        let beat_number = ((metronome.next_beat_time - metronome.song_start_time) / BEAT_INTERVAL) + 1.0;
+    let click = metronome.next_click;
+    let click_time = metronome.song_start_time + tempo_map.beat_to_seconds(click);
+    if current_time >= click_time {
+        // The first beat of each measure gets the accented click
+        let is_downbeat = tempo_map.measure_position(click).1 == Beat::ZERO;
         
         // Visual metronome flash
         for mut sprite in flash_query.iter_mut() {
-            sprite.color = Color::srgb(1.0, 1.0, 0.0); // Yellow flash
+            sprite.color = if is_downbeat {
+                Color::srgb(1.0, 0.6, 0.0) // Orange flash on downbeats
+            } else {
+                Color::srgb(1.0, 1.0, 0.0) // Yellow flash
+            };
         }
         
         // Spawn a timer to turn off the flash
@@ -678,24 +1275,32 @@ pub fn metronome_system(
             },
         ));
         
-        // Console metronome for audio feedback + system bell
-        println!("🔔 METRONOME BEAT {:.0} at {:.3}s (expected at {:.3}s)", 
-                 beat_number, current_time, metronome.next_beat_time);
+        let signature = tempo_map.time_signature_at(click);
+        debug!("Metronome {} ({}/{} at {:.0} BPM) at {:.3}s (expected at {:.3}s)", 
+               beat_label(&tempo_map, click), signature.numerator, signature.denominator,
+               tempo_map.bpm_at(click), current_time, click_time);
         
-        // Try to play system bell sound (works on macOS)
-        std::process::Command::new("afplay")
-            .arg("/System/Library/Sounds/Tink.aiff")
-            .spawn()
-            .ok(); // Ignore errors if the sound file doesn't exist
+        let accent = metronome.accent_handle.clone().filter(|_| is_downbeat);
+        if let Some(sample) = accent.clone().or_else(|| metronome.audio_handle.clone()) {
+            let volume = if accent.is_some() { metronome.accent_volume } else { 1.0 };
+            metronome_channel.play(sample).with_volume(volume);
+        }
         
-        // Schedule the next beat
-        // Calculate precise metronome beat time to prevent drift
-        // Round to nearest beat interval to maintain precision
-        let beats_elapsed = ((current_time - metronome.song_start_time) / BEAT_INTERVAL).round();
-        metronome.next_beat_time = metronome.song_start_time + (beats_elapsed + 1.0) * BEAT_INTERVAL;
+        // Schedule the next click on the tempo map, skipping any a frame hitch jumped past
+        metronome.next_click = tempo_map.next_click(click);
+        while metronome.song_start_time + tempo_map.beat_to_seconds(metronome.next_click) < current_time {
+            metronome.next_click = tempo_map.next_click(metronome.next_click);
+        }
     }
 }
 
+// "measure 3, beat 2+1/2" (both counted from 1) for the debug logs
+fn beat_label(tempo_map: &TempoMap, beat: Beat) -> String {
+    let (measure, position) = tempo_map.measure_position(beat);
+    let counted_beat = position / tempo_map.time_signature_at(beat).beat_length() + Beat::whole(1);
+    format!("measure {}, beat {}", measure + 1, counted_beat)
+}
+
 // System to handle metronome flash timing
 pub fn handle_metronome_flash(
     mut commands: Commands,
@@ -1,6 +1,10 @@
 use bevy::prelude::*;
+use serde::de::{self, Deserializer, Visitor};
+use serde::{Deserialize, Serialize, Serializer};
+use std::fmt;
+use std::str::FromStr;
 
-#[derive(Clone, Copy, Debug)]
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
 pub enum NoteDuration {
     Whole,
     Half,
@@ -11,15 +15,29 @@ pub enum NoteDuration {
 }
 
 impl NoteDuration {
-    pub fn to_seconds(&self) -> f32 {
-        use crate::constants::*;
+    pub fn to_seconds(&self, bpm: f32) -> f32 {
+        self.to_beats().to_f32() * 60.0 / bpm
+    }
+    
+    // Length of the duration in beats (a beat is a quarter note)
+    pub fn to_beats(&self) -> Beat {
         match self {
-            NoteDuration::Whole => WHOLE_NOTE,
-            NoteDuration::Half => HALF_NOTE,
-            NoteDuration::Quarter => QUARTER_NOTE,
-            NoteDuration::Eighth => EIGHTH_NOTE,
-            NoteDuration::Sixteenth => SIXTEENTH_NOTE,
-            NoteDuration::Triplet => TRIPLET_NOTE,
+            NoteDuration::Whole => Beat::whole(4),
+            NoteDuration::Half => Beat::whole(2),
+            NoteDuration::Quarter => Beat::whole(1),
+            NoteDuration::Eighth => Beat::new(1, 2),
+            NoteDuration::Sixteenth => Beat::new(1, 4),
+            NoteDuration::Triplet => Beat::new(2, 3),
+        }
+    }
+    
+    // Classify an absolute beat position by the finest subdivision it sits on, which picks the note's colour
+    pub fn from_beat(beat: Beat) -> NoteDuration {
+        match beat.fract().denominator() {
+            1 => NoteDuration::Quarter,
+            2 => NoteDuration::Eighth,
+            denominator if denominator % 3 == 0 => NoteDuration::Triplet,
+            _ => NoteDuration::Sixteenth,
         }
     }
     
@@ -44,4 +62,324 @@ impl NoteDuration {
             NoteDuration::Triplet => Color::srgb(1.0, 1.0, 0.2), // Yellow - triplets/complex
         }
     }
-}
\ No newline at end of file
+}
+
+// Letter grade for a run, from its accuracy
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Grade {
+    SS,
+    S,
+    A,
+    B,
+    C,
+    D,
+}
+
+impl Grade {
+    // `accuracy` is a percentage, SS needs a flawless run
+    pub fn from_accuracy(accuracy: f32) -> Grade {
+        match accuracy {
+            accuracy if accuracy >= 100.0 => Grade::SS,
+            accuracy if accuracy >= 95.0 => Grade::S,
+            accuracy if accuracy >= 90.0 => Grade::A,
+            accuracy if accuracy >= 80.0 => Grade::B,
+            accuracy if accuracy >= 70.0 => Grade::C,
+            _ => Grade::D,
+        }
+    }
+    
+    pub fn name(&self) -> &'static str {
+        match self {
+            Grade::SS => "SS",
+            Grade::S => "S",
+            Grade::A => "A",
+            Grade::B => "B",
+            Grade::C => "C",
+            Grade::D => "D",
+        }
+    }
+    
+    pub fn color(&self) -> Color {
+        match self {
+            Grade::SS => Color::srgb(1.0, 1.0, 1.0), // White
+            Grade::S => Color::srgb(1.0, 0.85, 0.2), // Gold
+            Grade::A => Color::srgb(0.2, 1.0, 0.2),  // Green
+            Grade::B => Color::srgb(0.2, 0.6, 1.0),  // Blue
+            Grade::C => Color::srgb(0.8, 0.4, 1.0),  // Purple
+            Grade::D => Color::srgb(1.0, 0.2, 0.2),  // Red
+        }
+    }
+}
+
+// Exact position in a chart, counted in beats (quarter notes) from beat 0
+// Stored as a reduced fraction so triplets, sixteenths and 192nds all line up exactly
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct Beat {
+    numerator: i64,
+    denominator: i64, // Always positive
+}
+
+// Fractions tried (in order) when turning a float into a Beat, covering every common snap
+const SNAP_DENOMINATORS: [i64; 14] = [1, 2, 3, 4, 6, 8, 12, 16, 24, 32, 48, 64, 96, 192];
+const SNAP_TOLERANCE: f64 = 1e-3;
+const FALLBACK_DENOMINATOR: i64 = 960;
+
+impl Beat {
+    pub const ZERO: Beat = Beat { numerator: 0, denominator: 1 };
+    
+    pub fn new(numerator: i64, denominator: i64) -> Beat {
+        assert!(denominator != 0, "beat denominator can't be zero");
+        Beat::reduce(numerator as i128, denominator as i128).expect("beat out of range")
+    }
+    
+    // Reduced fraction, None when the denominator is zero or the result doesn't fit in i64
+    fn reduce(numerator: i128, denominator: i128) -> Option<Beat> {
+        if denominator == 0 {
+            return None;
+        }
+        let sign = denominator.signum();
+        let divisor = gcd(numerator.abs(), denominator.abs()).max(1);
+        Some(Beat {
+            numerator: i64::try_from(sign * numerator / divisor).ok()?,
+            denominator: i64::try_from(denominator.abs() / divisor).ok()?,
+        })
+    }
+    
+    // Arithmetic that gives None instead of overflowing, for values read from chart files
+    pub fn checked_add(self, other: Beat) -> Option<Beat> {
+        let (a, b) = (self.wide(), other.wide());
+        Beat::reduce(a.0 * b.1 + b.0 * a.1, a.1 * b.1)
+    }
+    
+    pub fn checked_sub(self, other: Beat) -> Option<Beat> {
+        let (a, b) = (self.wide(), other.wide());
+        Beat::reduce(a.0 * b.1 - b.0 * a.1, a.1 * b.1)
+    }
+    
+    pub fn checked_mul(self, other: Beat) -> Option<Beat> {
+        let (a, b) = (self.wide(), other.wide());
+        Beat::reduce(a.0 * b.0, a.1 * b.1)
+    }
+    
+    pub fn checked_div(self, other: Beat) -> Option<Beat> {
+        let (a, b) = (self.wide(), other.wide());
+        Beat::reduce(a.0 * b.1, a.1 * b.0)
+    }
+    
+    fn wide(self) -> (i128, i128) {
+        (self.numerator as i128, self.denominator as i128)
+    }
+    
+    pub fn whole(beats: i64) -> Beat {
+        Beat::new(beats, 1)
+    }
+    
+    // `numerator / denominator` of the way through a 4/4 measure
+    pub fn from_measure(measure: i64, numerator: i64, denominator: i64) -> Beat {
+        Beat::whole(measure * 4) + Beat::new(numerator * 4, denominator)
+    }
+    
+    // Snap a float beat to the simplest fraction it sits on (or the nearest 1/960th)
+    pub fn from_f32(beat: f32) -> Beat {
+        Beat::snap(beat, SNAP_TOLERANCE)
+    }
+    
+    // Same as `from_f32` with a custom tolerance in beats (for formats rounded to whole milliseconds)
+    pub fn snap(beat: f32, tolerance: f64) -> Beat {
+        let beat = beat as f64;
+        SNAP_DENOMINATORS
+            .iter()
+            .map(|&denominator| Beat::new((beat * denominator as f64).round() as i64, denominator))
+            .find(|snapped| (snapped.to_f64() - beat).abs() < tolerance)
+            .unwrap_or_else(|| Beat::new((beat * FALLBACK_DENOMINATOR as f64).round() as i64, FALLBACK_DENOMINATOR))
+    }
+    
+    pub fn numerator(&self) -> i64 {
+        self.numerator
+    }
+    
+    pub fn denominator(&self) -> i64 {
+        self.denominator
+    }
+    
+    pub fn to_f32(self) -> f32 {
+        self.to_f64() as f32
+    }
+    
+    pub fn to_f64(self) -> f64 {
+        self.numerator as f64 / self.denominator as f64
+    }
+    
+    // Whole beats, rounded towards negative infinity
+    pub fn floor(self) -> i64 {
+        self.numerator.div_euclid(self.denominator)
+    }
+    
+    // Whole beats, rounded towards positive infinity
+    pub fn ceil(self) -> i64 {
+        -(-self.numerator).div_euclid(self.denominator)
+    }
+    
+    // Position within the beat, always in [0, 1)
+    pub fn fract(self) -> Beat {
+        Beat::new(self.numerator.rem_euclid(self.denominator), self.denominator)
+    }
+    
+    // 4/4 measure the beat falls in
+    pub fn measure(self) -> i64 {
+        self.floor().div_euclid(4)
+    }
+    
+    // Nearest multiple of `grid`
+    pub fn quantize(self, grid: Beat) -> Beat {
+        let steps = (self.to_f64() / grid.to_f64()).round() as i64;
+        Beat::new(grid.numerator * steps, grid.denominator)
+    }
+}
+
+fn gcd(a: i128, b: i128) -> i128 {
+    if b == 0 { a } else { gcd(b, a % b) }
+}
+
+impl Default for Beat {
+    fn default() -> Self {
+        Beat::ZERO
+    }
+}
+
+impl From<Beat> for f64 {
+    fn from(beat: Beat) -> f64 {
+        beat.to_f64()
+    }
+}
+
+impl Ord for Beat {
+    fn cmp(&self, other: &Self) -> std::cmp::Ordering {
+        (self.numerator as i128 * other.denominator as i128).cmp(&(other.numerator as i128 * self.denominator as i128))
+    }
+}
+
+impl PartialOrd for Beat {
+    fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+// Worked out in i128 so only results that don't fit in a Beat overflow
+impl std::ops::Add for Beat {
+    type Output = Beat;
+    fn add(self, other: Beat) -> Beat {
+        self.checked_add(other).expect("beat overflow")
+    }
+}
+
+impl std::ops::Sub for Beat {
+    type Output = Beat;
+    fn sub(self, other: Beat) -> Beat {
+        self.checked_sub(other).expect("beat overflow")
+    }
+}
+
+impl std::ops::Mul for Beat {
+    type Output = Beat;
+    fn mul(self, other: Beat) -> Beat {
+        self.checked_mul(other).expect("beat overflow")
+    }
+}
+
+impl std::ops::Div for Beat {
+    type Output = Beat;
+    fn div(self, other: Beat) -> Beat {
+        self.checked_div(other).expect("beat overflow or division by zero")
+    }
+}
+
+impl std::ops::AddAssign for Beat {
+    fn add_assign(&mut self, other: Beat) {
+        *self = *self + other;
+    }
+}
+
+// Written as "12", "1/3" or "12+1/3"
+impl fmt::Display for Beat {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let whole = self.floor();
+        let fraction = self.fract();
+        if fraction.numerator == 0 {
+            write!(f, "{}", whole)
+        } else if whole == 0 {
+            write!(f, "{}/{}", fraction.numerator, fraction.denominator)
+        } else if whole < 0 {
+            write!(f, "{}/{}", self.numerator, self.denominator)
+        } else {
+            write!(f, "{}+{}/{}", whole, fraction.numerator, fraction.denominator)
+        }
+    }
+}
+
+impl FromStr for Beat {
+    type Err = String;
+    
+    fn from_str(value: &str) -> Result<Beat, String> {
+        let value = value.trim();
+        let invalid = || format!("invalid beat '{}'", value);
+        let parse = |number: &str| number.trim().parse::<i64>().map_err(|_| invalid());
+        
+        let (whole, fraction) = match value.split_once('+') {
+            Some((whole, fraction)) => (parse(whole)?, fraction),
+            None if value.contains('/') => (0, value),
+            None => return value.parse::<f32>().map(Beat::from_f32).map_err(|_| invalid()),
+        };
+        let (numerator, denominator) = fraction.split_once('/').ok_or_else(invalid)?;
+        let denominator = parse(denominator)?;
+        if denominator == 0 {
+            return Err(invalid());
+        }
+        let fraction = Beat::reduce(parse(numerator)? as i128, denominator as i128).ok_or_else(invalid)?;
+        Beat::whole(whole).checked_add(fraction).ok_or_else(invalid)
+    }
+}
+
+// Whole beats serialize as plain numbers, anything else as a fraction string;
+// plain float beats from older charts still load
+impl Serialize for Beat {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        if self.denominator == 1 {
+            serializer.serialize_i64(self.numerator)
+        } else {
+            serializer.collect_str(self)
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for Beat {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Beat, D::Error> {
+        struct BeatVisitor;
+        
+        impl Visitor<'_> for BeatVisitor {
+            type Value = Beat;
+            
+            fn expecting(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                f.write_str("a beat number or a fraction like \"12+1/3\"")
+            }
+            
+            fn visit_i64<E: de::Error>(self, value: i64) -> Result<Beat, E> {
+                Ok(Beat::whole(value))
+            }
+            
+            fn visit_u64<E: de::Error>(self, value: u64) -> Result<Beat, E> {
+                Ok(Beat::whole(value as i64))
+            }
+            
+            fn visit_f64<E: de::Error>(self, value: f64) -> Result<Beat, E> {
+                Ok(Beat::from_f32(value as f32))
+            }
+            
+            fn visit_str<E: de::Error>(self, value: &str) -> Result<Beat, E> {
+                value.parse().map_err(E::custom)
+            }
+        }
+        
+        deserializer.deserialize_any(BeatVisitor)
+    }
+}
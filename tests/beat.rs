@@ -0,0 +1,82 @@
+// Exact beat fractions: reduction, ordering, arithmetic and parsing
+
+use rusty_rhythm::*;
+
+#[test]
+fn fractions_are_kept_reduced() {
+    assert_eq!(Beat::new(6, 8), Beat::new(3, 4));
+    assert_eq!(Beat::new(3, -6), Beat::new(-1, 2));
+    assert_eq!(Beat::new(-3, -6).numerator(), 1);
+    assert_eq!(Beat::new(0, 7), Beat::ZERO);
+    assert_eq!(Beat::new(0, 7).denominator(), 1);
+    assert_eq!(Beat::from_measure(2, 1, 3), Beat::new(28, 3));
+}
+
+#[test]
+fn ordering_compares_values_not_fields() {
+    assert!(Beat::new(1, 3) < Beat::new(1, 2));
+    assert!(Beat::new(-1, 2) < Beat::ZERO);
+    assert!(Beat::new(2999, 3) < Beat::whole(1000));
+
+    let mut beats = vec![Beat::whole(2), Beat::new(3, 4), Beat::new(2, 3), Beat::new(-1, 4)];
+    beats.sort();
+    assert_eq!(beats, vec![Beat::new(-1, 4), Beat::new(2, 3), Beat::new(3, 4), Beat::whole(2)]);
+}
+
+#[test]
+fn arithmetic_stays_exact() {
+    // Three triplets make a beat without any float drift
+    let triplet = Beat::new(1, 3);
+    assert_eq!(triplet + triplet + triplet, Beat::whole(1));
+    assert_eq!(Beat::new(1, 3) + Beat::new(1, 4), Beat::new(7, 12));
+    assert_eq!(Beat::new(1, 2) - Beat::new(3, 4), Beat::new(-1, 4));
+    assert_eq!(Beat::new(2, 3) * Beat::new(3, 4), Beat::new(1, 2));
+    assert_eq!(Beat::new(1, 2) / Beat::new(1, 6), Beat::whole(3));
+
+    let mut beat = Beat::ZERO;
+    for _ in 0..48 {
+        beat += Beat::new(1, 48);
+    }
+    assert_eq!(beat, Beat::whole(1));
+
+    let beat = Beat::new(-7, 3);
+    assert_eq!((beat.floor(), beat.ceil()), (-3, -2));
+    assert_eq!(beat.fract(), Beat::new(2, 3));
+    assert_eq!(Beat::new(17, 2).measure(), 2);
+}
+
+#[test]
+fn simfile_decimals_snap_to_fractions() {
+    // Simfiles write beats with three decimals
+    assert_eq!("1.333".parse::<Beat>().unwrap(), Beat::new(4, 3));
+    assert_eq!("0.667".parse::<Beat>().unwrap(), Beat::new(2, 3));
+    assert_eq!("2.250".parse::<Beat>().unwrap(), Beat::new(9, 4));
+    assert_eq!("0.021".parse::<Beat>().unwrap(), Beat::new(1, 48));
+    assert_eq!(Beat::from_f32(0.1), Beat::new(96, 960));
+}
+
+#[test]
+fn fraction_strings_round_trip() {
+    for beat in [Beat::whole(12), Beat::new(1, 3), Beat::new(37, 3), Beat::new(-5, 4)] {
+        assert_eq!(beat.to_string().parse::<Beat>().unwrap(), beat);
+    }
+    assert_eq!(Beat::new(37, 3).to_string(), "12+1/3");
+    assert_eq!("12 + 1/3".parse::<Beat>().unwrap(), Beat::new(37, 3));
+    assert!("1/0".parse::<Beat>().is_err());
+    assert!("one".parse::<Beat>().is_err());
+}
+
+#[test]
+fn values_too_big_for_a_beat_are_parse_errors() {
+    assert!("9223372036854775807+1/2".parse::<Beat>().is_err());
+    assert!("9223372036854775807+1/1".parse::<Beat>().is_err());
+    assert!("-9223372036854775808/-1".parse::<Beat>().is_err());
+    assert!("5+1/0".parse::<Beat>().is_err());
+    assert_eq!("9223372036854775806+1/1".parse::<Beat>().unwrap(), Beat::whole(i64::MAX));
+
+    // Cross multiplying big denominators doesn't overflow when the result fits
+    let big = Beat::new(1, i64::MAX);
+    assert_eq!(big + big, Beat::new(2, i64::MAX));
+    assert_eq!(Beat::whole(i64::MAX).checked_add(Beat::whole(1)), None);
+    assert_eq!(Beat::whole(1).checked_div(Beat::ZERO), None);
+}
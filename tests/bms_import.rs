@@ -0,0 +1,110 @@
+// BMS import: timing channels, long notes, #RANDOM branches and keysounds
+
+use rusty_rhythm::charts::{bms, ChartError};
+use rusty_rhythm::*;
+
+const BMS: &str = "#TITLE BMS Test
+#ARTIST Rusty Rhythm
+#PLAYLEVEL 5
+#BPM 120
+#WAV01 kick.wav
+#WAV02 snare.wav
+#WAV03 hold.wav
+#BPM01 240.5
+#STOP01 96
+#LNOBJ ZZ
+
+#00011:01000200
+#00012:01ZZ
+#00003:00B4
+#00102:0.75
+#00101:02
+#00109:01
+#00153:0303
+#00208:01
+
+#RANDOM 2
+#IF 1
+#00014:01
+#ENDIF
+#IF 2
+#00013:0001
+#ENDIF
+";
+
+#[test]
+fn bpm_channels_change_the_tempo() {
+    let chart = bms::parse_bms(BMS).unwrap();
+    assert_eq!(chart.bpm, 120.0);
+    let changes: Vec<_> = chart.bpm_changes.iter().map(|change| (change.beat, change.bpm)).collect();
+    // Channel 03 "B4" is hex for 180, channel 08 points at #BPM01
+    assert_eq!(changes, vec![(Beat::whole(2), 180.0), (Beat::whole(7), 240.5)]);
+}
+
+#[test]
+fn stops_are_converted_from_192nds_of_a_measure() {
+    let chart = bms::parse_bms(BMS).unwrap();
+    assert_eq!(chart.stops.len(), 1);
+    assert_eq!(chart.stops[0].beat, Beat::whole(4));
+    // 96/192 of a measure is 2 beats, at 180 BPM that's 2/3 of a second
+    assert!((chart.stops[0].duration - 2.0 / 3.0).abs() < 1e-5);
+}
+
+#[test]
+fn measure_lengths_become_time_signatures() {
+    let chart = bms::parse_bms(BMS).unwrap();
+    let signatures: Vec<_> = chart
+        .time_signatures
+        .iter()
+        .map(|signature| (signature.beat, signature.numerator, signature.denominator))
+        .collect();
+    assert_eq!(signatures, vec![(Beat::whole(4), 3, 4), (Beat::whole(7), 4, 4)]);
+}
+
+#[test]
+fn long_notes_keysounds_and_random_branches() {
+    let chart = bms::parse_bms(BMS).unwrap();
+    assert_eq!(chart.metadata.title, "BMS Test");
+    assert_eq!(chart.metadata.level, 5);
+    assert_eq!(chart.keysounds, vec!["kick.wav", "snare.wav", "hold.wav"]);
+
+    let notes: Vec<_> = chart.notes.iter().map(|note| (note.beat, note.lane, note.end_beat, note.keysound)).collect();
+    assert_eq!(
+        notes,
+        vec![
+            (Beat::ZERO, 0, None, Some(0)),
+            // #LNOBJ ZZ ends the note before it instead of being a note
+            (Beat::ZERO, 1, Some(Beat::whole(2)), Some(0)),
+            // Only the #IF 1 branch is read
+            (Beat::ZERO, 3, None, Some(0)),
+            (Beat::whole(2), 0, None, Some(1)),
+            // #LNTYPE 1 pair on channel 53, inside the 3 beat measure
+            (Beat::whole(4), 2, Some(Beat::new(11, 2)), Some(2)),
+        ]
+    );
+
+    assert_eq!(chart.background_sounds.len(), 1);
+    assert_eq!(chart.background_sounds[0].beat, Beat::whole(4));
+    assert_eq!(chart.background_sounds[0].keysound, 1);
+}
+
+#[test]
+fn lntype_2_merges_held_cells_into_one_long_note() {
+    // Beats 1-4, the whole 3 beat measure after it, then a separate note on beats 9-11
+    let source = "#BPM 120
+#LNTYPE 2
+#00052:00010101
+#00102:0.75
+#00152:01
+#00252:0001
+";
+    let chart = bms::parse_bms(source).unwrap();
+    let notes: Vec<_> = chart.notes.iter().map(|note| (note.beat, note.lane, note.end_beat)).collect();
+    assert_eq!(notes, vec![(Beat::whole(1), 1, Some(Beat::whole(7))), (Beat::whole(9), 1, Some(Beat::whole(11)))]);
+}
+
+#[test]
+fn unknown_long_note_types_are_rejected() {
+    let source = BMS.replace("#LNOBJ ZZ", "#LNTYPE 3");
+    assert!(matches!(bms::parse_bms(&source), Err(ChartError::Parse(_))));
+}
@@ -0,0 +1,273 @@
+// Charts written by the exporters must load back the same through the importers
+
+use rusty_rhythm::charts::{json, osu, sm};
+use rusty_rhythm::*;
+use std::path::Path;
+
+const SIMFILE: &str = "#TITLE:Round Trip;
+#ARTIST:Rusty Rhythm;
+#CREDIT:Tester;
+#MUSIC:song.ogg;
+#OFFSET:-0.250;
+#BPMS:0.000=120.000,8.000=180.000;
+#STOPS:4.000=0.500;
+#NOTES:
+     dance-single:
+     Tester:
+     Hard:
+     7:
+     0,0,0,0,0:
+1000
+0100
+0010
+0001
+,
+1000
+0000
+0000
+0200
+0000
+0000
+0001
+0000
+0000
+0000
+0000
+0300
+,
+1001
+0000
+0000
+0000
+0000
+0000
+0000
+0000
+0000
+0000
+0000
+0000
+0000
+0000
+0000
+0110
+;
+#NOTES:
+     dance-single:
+     Tester:
+     Easy:
+     2:
+     0,0,0,0,0:
+1000
+0000
+0000
+0000
+;
+";
+
+const BEATMAP: &str = "osu file format v14
+
+[General]
+AudioFilename: audio.mp3
+Mode: 3
+
+[Metadata]
+Title:Round Trip
+Artist:Rusty Rhythm
+Creator:Tester
+Version:4K Hard
+
+[Difficulty]
+CircleSize:4
+OverallDifficulty:8
+
+[TimingPoints]
+500,500,4,1,0,100,1,0
+2500,-50,4,1,0,100,0,0
+4500,250,4,1,0,100,1,0
+
+[HitObjects]
+64,192,500,1,0,0:0:0:0:
+192,192,1000,1,0,0:0:0:0:
+320,192,1500,128,0,2500:0:0:0:0:
+448,192,2750,1,0,0:0:0:0:
+64,192,4750,1,0,0:0:0:0:
+";
+
+fn assert_same_timing(expected: &Chart, actual: &Chart) {
+    let (expected_map, actual_map) = (expected.tempo_map(), actual.tempo_map());
+    assert_eq!(expected.notes.len(), actual.notes.len());
+
+    for (expected_note, actual_note) in expected.notes.iter().zip(&actual.notes) {
+        assert_eq!(expected_note.lane, actual_note.lane);
+        let expected_time = expected_map.beat_to_seconds(expected_note.beat);
+        let actual_time = actual_map.beat_to_seconds(actual_note.beat);
+        assert!((expected_time - actual_time).abs() < 0.002, "note at {}s came back at {}s", expected_time, actual_time);
+
+        let end_time = |map: &TempoMap, note: &ChartNote| note.end_beat.map(|end| map.beat_to_seconds(end));
+        match (end_time(&expected_map, expected_note), end_time(&actual_map, actual_note)) {
+            (Some(expected_end), Some(actual_end)) => assert!((expected_end - actual_end).abs() < 0.002),
+            (expected_end, actual_end) => assert_eq!(expected_end, actual_end),
+        }
+    }
+}
+
+#[test]
+fn json_round_trip_keeps_every_field() {
+    let mut chart = sm::parse_sm(SIMFILE).unwrap().remove(0);
+    chart.scroll_velocities.push(ScrollVelocity { beat: Beat::whole(2), multiplier: 1.5 });
+    chart.keysounds.push("kick.wav".to_string());
+    chart.notes[0].keysound = Some(0);
+    chart.background_sounds.push(BackgroundSound { beat: Beat::whole(1), keysound: 0 });
+
+    let written = json::write_json(&chart).unwrap();
+    assert_eq!(json::parse_json(&written).unwrap(), chart);
+}
+
+#[test]
+fn sm_round_trip_keeps_every_difficulty() {
+    let charts = sm::parse_sm(SIMFILE).unwrap();
+    assert_eq!(charts.len(), 2);
+
+    let written = sm::write_sm(&charts).unwrap();
+    assert_eq!(sm::parse_sm(&written).unwrap(), charts);
+}
+
+#[test]
+fn sm_writer_rejects_charts_with_different_timing() {
+    let mut charts = sm::parse_sm(SIMFILE).unwrap();
+    charts[1].bpm = 90.0;
+    assert!(sm::write_sm(&charts).is_err());
+}
+
+#[test]
+fn sm_writer_rejects_notes_it_would_have_to_move() {
+    // Quintuplets are off the 192nd grid
+    let mut chart = sm::parse_sm(SIMFILE).unwrap().remove(0);
+    chart.notes.push(ChartNote { beat: Beat::new(1, 5), lane: 2, kind: NoteKind::Tap, end_beat: None, keysound: None });
+    assert!(sm::write_sm(&[chart.validate().unwrap()]).is_err());
+
+    // A hold's end and the next note in its lane would share a cell
+    let mut chart = sm::parse_sm(SIMFILE).unwrap().remove(0);
+    let hold = chart.notes.iter().find(|note| note.end_beat.is_some()).unwrap();
+    let collision = ChartNote { beat: hold.end_beat.unwrap(), lane: hold.lane, kind: NoteKind::Tap, end_beat: None, keysound: None };
+    chart.notes.push(collision);
+    assert!(sm::write_sm(&[chart.validate().unwrap()]).is_err());
+
+    // Timing points have to be on the grid too
+    let mut chart = sm::parse_sm(SIMFILE).unwrap().remove(0);
+    chart.stops.push(Stop { beat: Beat::new(21, 5), duration: 0.1 });
+    assert!(sm::write_sm(&[chart.validate().unwrap()]).is_err());
+}
+
+#[test]
+fn sm_writer_rounds_the_offset_without_negative_zero() {
+    let mut chart = sm::parse_sm(SIMFILE).unwrap().remove(0);
+    chart.offset = 0.0;
+    assert!(sm::write_sm(std::slice::from_ref(&chart)).unwrap().contains("#OFFSET:0.000;"));
+    chart.offset = 0.1234;
+    assert!(sm::write_sm(std::slice::from_ref(&chart)).unwrap().contains("#OFFSET:-0.123;"));
+}
+
+#[test]
+fn osu_round_trip_keeps_note_times() {
+    let chart = osu::parse_osu(BEATMAP).unwrap();
+    let reloaded = osu::parse_osu(&osu::write_osu(&chart).unwrap()).unwrap();
+
+    assert_eq!(reloaded.metadata, chart.metadata);
+    assert_eq!(reloaded.scroll_velocities, chart.scroll_velocities);
+    assert_same_timing(&chart, &reloaded);
+}
+
+#[test]
+fn simfile_exports_to_osu_with_stops() {
+    let chart = sm::parse_sm(SIMFILE).unwrap().remove(0);
+    let converted = osu::parse_osu(&write_chart(&chart, "osu").unwrap()).unwrap();
+    assert_same_timing(&chart, &converted);
+}
+
+#[test]
+fn beatmap_exports_to_json_and_sm() {
+    let chart = osu::parse_osu(BEATMAP).unwrap();
+
+    let from_json = Chart::parse(&write_chart(&chart, "json").unwrap(), "json").unwrap();
+    assert_eq!(from_json, chart);
+
+    let from_sm = Chart::parse(&write_chart(&chart, "sm").unwrap(), "sm").unwrap();
+    assert_same_timing(&chart, &from_sm);
+}
+
+#[test]
+fn json_charts_have_their_own_extension() {
+    assert_eq!(file_extension(Path::new("songs/demo/demo.chart.json")), "chart.json");
+    assert_eq!(file_extension(Path::new("songs/demo/Demo.Chart.JSON")), "chart.json");
+    assert_eq!(file_extension(Path::new("settings.json")), "json");
+    assert!(CHART_EXTENSIONS.contains(&"chart.json") && !CHART_EXTENSIONS.contains(&"json"));
+    assert!(Chart::load(Path::new("assets/charts/demo.chart.json")).is_ok());
+}
+
+#[test]
+fn mixed_triplets_and_sixteenths_stay_exact() {
+    let group = [
+        (NoteDuration::Triplet, 0),
+        (NoteDuration::Triplet, 1),
+        (NoteDuration::Triplet, 2),
+        (NoteDuration::Sixteenth, 3),
+        (NoteDuration::Sixteenth, 2),
+        (NoteDuration::Sixteenth, 1),
+        (NoteDuration::Sixteenth, 0),
+    ];
+    let mut beat = Beat::ZERO;
+    let notes = group
+        .iter()
+        .cycle()
+        .take(group.len() * 1000)
+        .map(|&(duration, lane)| {
+            let note = ChartNote { beat, lane, kind: NoteKind::Tap, end_beat: None, keysound: None };
+            beat += duration.to_beats();
+            note
+        })
+        .collect();
+    let mut chart = sm::parse_sm(SIMFILE).unwrap().remove(0);
+    chart.notes = notes;
+
+    // Every group is exactly 3 beats, however far into the chart it is
+    assert_eq!(chart.notes[group.len() * 999].beat, Beat::whole(2997));
+    assert_eq!(chart.notes[group.len() * 999 + 1].beat, Beat::new(8993, 3));
+
+    let written = sm::write_sm(std::slice::from_ref(&chart)).unwrap();
+    assert_eq!(sm::parse_sm(&written).unwrap()[0].notes, chart.notes);
+    let written = json::write_json(&chart).unwrap();
+    assert_eq!(json::parse_json(&written).unwrap(), chart);
+}
+
+#[test]
+fn delays_and_time_signatures_survive_export() {
+    let mut chart = sm::parse_sm(SIMFILE).unwrap().remove(0);
+    chart.delays.push(Delay { beat: Beat::whole(6), duration: 0.25 });
+    chart.time_signatures.push(TimeSignature { beat: Beat::whole(4), numerator: 3, denominator: 4 });
+
+    let from_sm = sm::parse_sm(&sm::write_sm(std::slice::from_ref(&chart)).unwrap()).unwrap().remove(0);
+    assert_eq!(from_sm, chart);
+
+    let from_osu = osu::parse_osu(&osu::write_osu(&chart).unwrap()).unwrap();
+    assert_same_timing(&chart, &from_osu);
+}
+
+#[test]
+fn note_kinds_survive_sm_and_osu_keeps_the_playable_ones() {
+    let mut chart = sm::parse_sm(SIMFILE).unwrap().remove(0);
+    chart.notes.push(ChartNote { beat: Beat::new(9, 2), lane: 1, kind: NoteKind::Mine, end_beat: None, keysound: None });
+    chart.notes.push(ChartNote { beat: Beat::new(19, 4), lane: 2, kind: NoteKind::Fake, end_beat: None, keysound: None });
+    chart.notes.push(ChartNote { beat: Beat::whole(5), lane: 3, kind: NoteKind::Lift, end_beat: None, keysound: None });
+    chart.notes.push(ChartNote { beat: Beat::whole(6), lane: 0, kind: NoteKind::Roll, end_beat: Some(Beat::new(15, 2)), keysound: None });
+    let chart = chart.validate().unwrap();
+
+    let from_sm = sm::parse_sm(&sm::write_sm(std::slice::from_ref(&chart)).unwrap()).unwrap().remove(0);
+    assert_eq!(from_sm, chart);
+    let from_json = json::parse_json(&json::write_json(&chart).unwrap()).unwrap();
+    assert_eq!(from_json, chart);
+
+    let from_osu = osu::parse_osu(&osu::write_osu(&chart).unwrap()).unwrap();
+    assert_eq!(from_osu.notes.len(), chart.notes.iter().filter(|note| note.kind.is_judged()).count());
+}
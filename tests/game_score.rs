@@ -0,0 +1,78 @@
+// Accuracy, grades and combos over a run, holds count their head and tail
+
+use rusty_rhythm::charts::sm;
+use rusty_rhythm::*;
+
+// Two taps and a hold, so four judgements from three notes
+const SIMFILE: &str = "#TITLE:Score;
+#BPMS:0.000=120.000;
+#NOTES:
+     dance-single:
+     :
+     Easy:
+     1:
+     0,0,0,0,0:
+1000
+0200
+0000
+0301
+;
+";
+
+fn game_score() -> GameScore {
+    GameScore::for_chart(&sm::parse_sm(SIMFILE).unwrap().remove(0))
+}
+
+#[test]
+fn a_full_combo_through_holds_fits_the_chart() {
+    let windows = JudgementWindows::standard();
+    let mut score = game_score();
+    assert_eq!((score.total_notes, score.total_judgements), (3, 4));
+    assert_eq!((score.accuracy(), score.grade()), (100.0, Grade::SS));
+
+    for _ in 0..score.total_judgements {
+        score.record_hit(0, &windows);
+    }
+    assert_eq!(score.max_combo, score.total_judgements);
+    assert_eq!((score.accuracy(), score.grade()), (100.0, Grade::SS));
+}
+
+#[test]
+fn accuracy_and_grade_follow_the_tiers() {
+    let windows = JudgementWindows::standard();
+    let mut score = game_score();
+    // 100 + 100 + 50 + 20 out of 400
+    for tier in [0, 0, 1, 2] {
+        score.record_hit(tier, &windows);
+    }
+    assert_eq!(score.counts, vec![2, 1, 1]);
+    assert_eq!(score.accuracy(), 67.5);
+    assert_eq!(score.grade(), Grade::D);
+    // OKAY broke the combo at the end
+    assert_eq!((score.streak, score.max_combo), (0, 3));
+
+    let mut score = game_score();
+    for tier in [0, 0, 0, 1] {
+        score.record_hit(tier, &windows);
+    }
+    assert_eq!(score.accuracy(), 87.5);
+    assert_eq!(score.grade(), Grade::B);
+}
+
+#[test]
+fn misses_and_drops_reset_the_combo() {
+    let windows = JudgementWindows::standard();
+    let mut score = game_score();
+    score.record_hit(0, &windows);
+    score.record_hit(0, &windows);
+    score.record_drop(&windows);
+    score.record_miss(&windows);
+    assert_eq!((score.streak, score.max_combo, score.drops, score.misses), (0, 2, 1, 1));
+    assert_eq!(score.accuracy(), 50.0);
+
+    // Mines cost score but not accuracy or combo
+    score.record_hit(0, &windows);
+    score.record_mine();
+    assert_eq!((score.streak, score.mines_hit), (1, 1));
+    assert_eq!(score.accuracy(), 60.0);
+}
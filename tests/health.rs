@@ -0,0 +1,106 @@
+// Life gauge drain models, no-fail and what happens after the run fails
+
+use rusty_rhythm::*;
+
+fn health(drain: DrainModel, no_fail: bool) -> Health {
+    Health::new(&LifeSettings { drain, no_fail })
+}
+
+fn close(actual: f32, expected: f32) {
+    assert!((actual - expected).abs() < 1e-5, "expected {}, got {}", expected, actual);
+}
+
+#[test]
+fn normal_drain_refills_on_good_hits() {
+    let mut health = health(DrainModel::Normal, false);
+    health.miss();
+    close(health.value, 1.0 - MISS_HEALTH_PENALTY);
+    health.hit(false);
+    close(health.value, 1.0 - MISS_HEALTH_PENALTY + HEALTH_HIT_GAIN);
+    health.hit(true);
+    health.mine();
+    close(health.value, 1.0 - MISS_HEALTH_PENALTY + HEALTH_HIT_GAIN - HEALTH_SLOPPY_PENALTY - MINE_HEALTH_PENALTY);
+
+    // The gauge never goes over full
+    for _ in 0..100 {
+        health.hit(false);
+    }
+    assert_eq!(health.value, 1.0);
+
+    while !health.failed {
+        health.miss();
+    }
+    assert_eq!(health.value, 0.0);
+}
+
+#[test]
+fn sudden_death_fails_on_the_first_combo_break() {
+    let mut health = health(DrainModel::SuddenDeath, false);
+    health.hit(false);
+    assert_eq!((health.value, health.failed), (1.0, false));
+    health.hit(true);
+    assert!(health.failed);
+
+    for fail in [Health::miss, Health::mine] {
+        let mut health = Health::new(&LifeSettings { drain: DrainModel::SuddenDeath, no_fail: false });
+        fail(&mut health);
+        assert!(health.failed);
+    }
+}
+
+#[test]
+fn battery_loses_a_life_per_miss_and_never_refills() {
+    let mut health = health(DrainModel::Battery { lives: 4 }, false);
+    assert_eq!(health.lives, 4);
+    health.miss();
+    health.mine();
+    assert_eq!(health.lives, 2);
+    close(health.value, 0.5);
+
+    // Hits, sloppy or not, leave the lives alone
+    health.hit(false);
+    health.hit(true);
+    assert_eq!(health.lives, 2);
+
+    health.miss();
+    assert!(!health.failed);
+    health.miss();
+    assert_eq!((health.lives, health.failed), (0, true));
+
+    health.reset();
+    assert_eq!((health.lives, health.value, health.failed), (4, 1.0, false));
+    // A battery always has at least one life
+    assert_eq!(Health::new(&LifeSettings { drain: DrainModel::Battery { lives: 0 }, no_fail: false }).lives, 1);
+}
+
+#[test]
+fn no_fail_keeps_playing_on_an_empty_gauge() {
+    let mut health = health(DrainModel::Normal, true);
+    for _ in 0..20 {
+        health.miss();
+    }
+    assert_eq!((health.value, health.failed), (0.0, false));
+    health.hit(false);
+    close(health.value, HEALTH_HIT_GAIN);
+
+    let mut health = Health::new(&LifeSettings { drain: DrainModel::SuddenDeath, no_fail: true });
+    health.miss();
+    assert_eq!((health.value, health.failed), (0.0, false));
+}
+
+#[test]
+fn a_failed_run_stays_frozen() {
+    let mut health = health(DrainModel::Normal, false);
+    while !health.failed {
+        health.mine();
+    }
+    health.hit(false);
+    health.hit(false);
+    assert_eq!((health.value, health.failed), (0.0, true));
+
+    let mut health = Health::new(&LifeSettings { drain: DrainModel::Battery { lives: 1 }, no_fail: false });
+    health.miss();
+    health.miss();
+    health.hit(false);
+    assert_eq!((health.lives, health.value, health.failed), (0, 0.0, true));
+}
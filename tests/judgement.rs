@@ -0,0 +1,108 @@
+// Judgement presets keep their published windows, and bad custom tiers fall back to the defaults
+
+use rusty_rhythm::*;
+
+fn tiers(windows: &JudgementWindows) -> Vec<(&str, f32, u32, bool)> {
+    windows.tiers.iter().map(|tier| (tier.name.as_str(), tier.window_ms, tier.score, tier.breaks_combo)).collect()
+}
+
+fn close(actual: &[f32], expected: &[f32]) {
+    assert_eq!(actual.len(), expected.len());
+    for (actual, expected) in actual.iter().zip(expected) {
+        assert!((actual - expected).abs() < 1e-3, "expected {:?}, got {:?}", expected, actual);
+    }
+}
+
+fn window_ms(windows: &JudgementWindows) -> Vec<f32> {
+    windows.tiers.iter().map(|tier| tier.window_ms).collect()
+}
+
+#[test]
+fn simple_presets_keep_their_windows() {
+    let score_tiers = |windows: [f32; 3]| {
+        vec![("PERFECT!", windows[0], 100, false), ("GOOD", windows[1], 50, false), ("OKAY", windows[2], 20, true)]
+    };
+    assert_eq!(tiers(&JudgementWindows::lenient()), score_tiers([75.0, 150.0, 225.0]));
+    assert_eq!(tiers(&JudgementWindows::standard()), score_tiers([50.0, 100.0, 150.0]));
+    assert_eq!(tiers(&JudgementWindows::strict()), score_tiers([30.0, 60.0, 100.0]));
+    assert_eq!(JudgementWindows::default(), JudgementWindows::standard());
+    assert_eq!(JudgementPreset::default().windows(), JudgementWindows::standard());
+}
+
+#[test]
+fn osu_mania_windows_tighten_with_od() {
+    let od8 = JudgementWindows::osu_mania(8.0);
+    assert_eq!(od8.name, "osu!mania OD8");
+    close(&window_ms(&od8), &[16.0, 40.0, 73.0, 103.0, 127.0]);
+    let scores: Vec<u32> = od8.tiers.iter().map(|tier| tier.score).collect();
+    assert_eq!(scores, vec![320, 300, 200, 100, 50]);
+    assert!(od8.tiers.iter().all(|tier| !tier.breaks_combo));
+
+    close(&window_ms(&JudgementWindows::osu_mania(0.0)), &[16.0, 64.0, 97.0, 127.0, 151.0]);
+    // OD is clamped to 0-10
+    assert_eq!(JudgementWindows::osu_mania(14.0), JudgementWindows::osu_mania(10.0));
+    assert_eq!(JudgementWindows::osu_mania(-2.0), JudgementWindows::osu_mania(0.0));
+}
+
+#[test]
+fn etterna_judges_scale_the_j4_windows() {
+    let j4 = JudgementWindows::etterna(4);
+    assert_eq!(j4.name, "Etterna J4");
+    assert_eq!(
+        tiers(&j4),
+        vec![
+            ("MARVELOUS", 22.5, 300, false),
+            ("PERFECT", 45.0, 300, false),
+            ("GREAT", 90.0, 200, false),
+            ("GOOD", 135.0, 100, true),
+            ("BAD", 180.0, 50, true),
+        ]
+    );
+    close(&window_ms(&JudgementWindows::etterna(1)), &[33.75, 67.5, 135.0, 202.5, 270.0]);
+    close(&window_ms(&JudgementWindows::etterna(7)), &[11.25, 22.5, 45.0, 67.5, 90.0]);
+    close(&window_ms(&JudgementWindows::etterna(9)), &[4.5, 9.0, 18.0, 27.0, 36.0]);
+    // Judges are clamped to 1-9
+    assert_eq!(JudgementWindows::etterna(0), JudgementWindows::etterna(1));
+    assert_eq!(JudgementWindows::etterna(12), JudgementWindows::etterna(9));
+}
+
+fn custom(windows: &[f32]) -> JudgementPreset {
+    let tiers = windows
+        .iter()
+        .enumerate()
+        .map(|(index, &window_ms)| JudgementTier { name: format!("T{}", index), window_ms, score: 10, breaks_combo: false, color: [1.0; 3] })
+        .collect();
+    JudgementPreset::Custom { name: "Custom".to_string(), tiers }
+}
+
+#[test]
+fn custom_tiers_have_to_be_ordered_and_positive() {
+    assert!(custom(&[20.0, 40.0, 80.0]).validate().is_ok());
+    assert!(custom(&[40.0, 20.0]).validate().is_err());
+    assert!(custom(&[20.0, 20.0]).validate().is_err());
+    assert!(custom(&[-5.0, 20.0]).validate().is_err());
+    assert!(custom(&[0.0]).validate().is_err());
+    assert!(custom(&[f32::NAN]).validate().is_err());
+    assert!(custom(&[]).validate().is_err());
+    assert!(JudgementPreset::Strict.validate().is_ok());
+}
+
+#[test]
+fn invalid_custom_tiers_load_as_the_defaults() {
+    let path = std::env::temp_dir().join(format!("rusty_rhythm_settings_{}.yaml", std::process::id()));
+    let yaml = "judgement:
+  preset: custom
+  name: Backwards
+  tiers:
+    - { name: GOOD, window_ms: 100, score: 50 }
+    - { name: GREAT, window_ms: 50, score: 100 }
+scoring: ex
+";
+    std::fs::write(&path, yaml).unwrap();
+    let settings = Settings::load(&path);
+    std::fs::remove_file(&path).unwrap();
+
+    assert_eq!(settings.judgement, JudgementPreset::Standard);
+    // The rest of the file still applies
+    assert_eq!(settings, Settings { scoring: ScoringKind::Ex, ..Settings::default() });
+}
@@ -0,0 +1,112 @@
+// Song folders found by the library scan, and the labels difficulties get inside a chart file
+
+use rusty_rhythm::*;
+use std::fs;
+use std::path::PathBuf;
+
+fn chart(difficulty: &str) -> Chart {
+    let mut chart = Chart::parse(&fs::read_to_string("assets/charts/demo.chart.json").unwrap(), "chart.json").unwrap();
+    chart.metadata.difficulty = difficulty.to_string();
+    chart
+}
+
+#[test]
+fn labels_are_numbered_when_names_repeat_or_are_missing() {
+    let charts = [chart("Hard"), chart(""), chart("Hard"), chart("Easy"), chart(""), chart("Hard")];
+    assert_eq!(difficulty_labels(&charts), vec!["Hard", "Chart2", "Hard2", "Easy", "Chart5", "Hard3"]);
+    assert_eq!(difficulty_labels(&[chart("Chart2"), chart("")]), vec!["Chart2", "Chart22"]);
+    assert!(difficulty_labels(&[]).is_empty());
+}
+
+const SIMFILE: &str = "#TITLE:Zebra Crossing;
+#ARTIST:Rusty Rhythm;
+#BPMS:0.000=120.000;
+#NOTES:
+     dance-single:
+     :
+     Hard:
+     9:
+     0,0,0,0,0:
+1000
+0100
+0010
+0001
+;
+#NOTES:
+     dance-single:
+     :
+     Easy:
+     2:
+     0,0,0,0,0:
+1000
+0000
+0000
+0000
+;
+";
+
+// A songs folder under the temp dir, removed when the test is done
+struct Assets(PathBuf);
+
+impl Assets {
+    fn new(name: &str) -> Self {
+        let dir = std::env::temp_dir().join(format!("rusty_rhythm_{}_{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        Assets(dir)
+    }
+
+    fn file(&self, path: &str, contents: &str) {
+        let path = self.0.join(path);
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(path, contents).unwrap();
+    }
+}
+
+impl Drop for Assets {
+    fn drop(&mut self) {
+        let _ = fs::remove_dir_all(&self.0);
+    }
+}
+
+#[test]
+fn scan_finds_songs_with_audio_and_a_chart_that_loads() {
+    let assets = Assets::new("scan");
+    assets.file("songs/zebra/song.ogg", "");
+    assets.file("songs/zebra/zebra.sm", SIMFILE);
+    assets.file("songs/apple/audio.mp3", "");
+    assets.file("songs/apple/apple.chart.json", &fs::read_to_string("assets/charts/demo.chart.json").unwrap());
+    assets.file("songs/apple/broken.osu", "not a beatmap");
+    // No audio, or no chart that loads
+    assets.file("songs/silent/silent.sm", SIMFILE);
+    assets.file("songs/broken/song.ogg", "");
+    assets.file("songs/broken/broken.sm", "#NOTES:oops");
+    assets.file("songs/notes.txt", "");
+
+    let library = SongLibrary::scan(&assets.0, "songs");
+    let folders: Vec<&str> = library.songs.iter().map(|song| song.folder.as_str()).collect();
+    assert_eq!(folders.len(), 2, "{:?}", folders);
+
+    // Sorted by title, and the simfile has its own
+    let zebra = &library.songs[1];
+    assert_eq!((zebra.folder.as_str(), zebra.title.as_str(), zebra.artist.as_str()), ("songs/zebra", "Zebra Crossing", "Rusty Rhythm"));
+    assert_eq!(zebra.bpm_label(), "120");
+    // Easiest first, each pointing at its labeled sub-asset
+    let difficulties: Vec<(&str, u32, usize, &str)> = zebra
+        .difficulties
+        .iter()
+        .map(|difficulty| (difficulty.name.as_str(), difficulty.level, difficulty.notes, difficulty.path.as_str()))
+        .collect();
+    assert_eq!(difficulties, vec![("Easy", 2, 1, "songs/zebra/zebra.sm#Easy"), ("Hard", 9, 4, "songs/zebra/zebra.sm#Hard")]);
+
+    // A single chart file isn't labeled, and the broken one next to it is skipped
+    let apple = &library.songs[0];
+    assert_eq!(apple.folder, "songs/apple");
+    assert_eq!(apple.difficulties.len(), 1);
+    assert_eq!(apple.difficulties[0].path, "songs/apple/apple.chart.json");
+}
+
+#[test]
+fn a_missing_songs_folder_is_an_empty_library() {
+    let assets = Assets::new("missing");
+    assert!(SongLibrary::scan(&assets.0, "songs").songs.is_empty());
+}
@@ -0,0 +1,109 @@
+// MIDI import: track selection, pitch lanes, tempo changes and quantizing
+
+use midly::num::{u15, u24, u28, u4, u7};
+use midly::{Format, Header, MetaMessage, MidiMessage, Smf, Timing, TrackEvent, TrackEventKind};
+use rusty_rhythm::charts::{midi, ChartError, MidiImportOptions};
+use rusty_rhythm::*;
+
+const TICKS_PER_BEAT: u16 = 96;
+
+fn meta(delta: u32, message: MetaMessage<'static>) -> TrackEvent<'static> {
+    TrackEvent { delta: u28::new(delta), kind: TrackEventKind::Meta(message) }
+}
+
+// Note-ons at absolute ticks, velocity 0 note-offs right after each one
+fn note_track(name: &'static [u8], notes: &[(u32, u8)]) -> Vec<TrackEvent<'static>> {
+    let mut track = vec![meta(0, MetaMessage::TrackName(name))];
+    let mut tick = 0;
+    for &(at, pitch) in notes {
+        let channel = u4::new(0);
+        track.push(TrackEvent {
+            delta: u28::new(at - tick),
+            kind: TrackEventKind::Midi { channel, message: MidiMessage::NoteOn { key: u7::new(pitch), vel: u7::new(100) } },
+        });
+        track.push(TrackEvent {
+            delta: u28::new(1),
+            kind: TrackEventKind::Midi { channel, message: MidiMessage::NoteOn { key: u7::new(pitch), vel: u7::new(0) } },
+        });
+        tick = at + 1;
+    }
+    track.push(meta(0, MetaMessage::EndOfTrack));
+    track
+}
+
+// A conductor track at 120 BPM going to 150 on beat 4, a drum track and a one note bass track
+fn song() -> Vec<u8> {
+    let conductor = vec![
+        meta(0, MetaMessage::TrackName(b"Song")),
+        meta(0, MetaMessage::Tempo(u24::new(500_000))),
+        meta(384, MetaMessage::Tempo(u24::new(400_000))),
+        meta(0, MetaMessage::EndOfTrack),
+    ];
+    let drums = note_track(b"Drums", &[(0, 36), (96, 38), (192, 42), (288, 46), (290, 46), (316, 36)]);
+    let bass = note_track(b"Bass", &[(0, 40)]);
+
+    let smf = Smf {
+        header: Header::new(Format::Parallel, Timing::Metrical(u15::new(TICKS_PER_BEAT))),
+        tracks: vec![conductor, drums, bass],
+    };
+    let mut bytes = Vec::new();
+    smf.write_std(&mut bytes).unwrap();
+    bytes
+}
+
+fn notes(chart: &Chart) -> Vec<(Beat, usize)> {
+    chart.notes.iter().map(|note| (note.beat, note.lane)).collect()
+}
+
+#[test]
+fn first_track_with_notes_is_split_across_lanes_by_pitch() {
+    let chart = midi::parse_midi(&song(), &MidiImportOptions::default()).unwrap();
+    assert_eq!(chart.metadata.title, "Song");
+    assert_eq!(chart.metadata.difficulty, "Drums");
+    // Pitches 36-46 split into 36-37, 38-39, 40-42 and 43-46
+    assert_eq!(
+        notes(&chart),
+        vec![
+            (Beat::ZERO, 0),
+            (Beat::whole(1), 1),
+            (Beat::whole(2), 2),
+            (Beat::whole(3), 3),
+            (Beat::new(13, 4), 0),
+        ]
+    );
+}
+
+#[test]
+fn tempo_events_become_bpm_changes() {
+    let chart = midi::parse_midi(&song(), &MidiImportOptions::default()).unwrap();
+    assert_eq!(chart.bpm, 120.0);
+    assert_eq!(chart.bpm_changes.len(), 1);
+    assert_eq!(chart.bpm_changes[0].beat, Beat::whole(4));
+    assert_eq!(chart.bpm_changes[0].bpm, 150.0);
+}
+
+#[test]
+fn notes_landing_on_the_same_grid_slot_are_merged() {
+    let options = MidiImportOptions { quantize: NoteDuration::Quarter, ..Default::default() };
+    let chart = midi::parse_midi(&song(), &options).unwrap();
+    // Ticks 288 and 290 both land on beat 3 in lane 3, tick 316 moves back to beat 3 in lane 0
+    assert_eq!(
+        notes(&chart),
+        vec![(Beat::ZERO, 0), (Beat::whole(1), 1), (Beat::whole(2), 2), (Beat::whole(3), 0), (Beat::whole(3), 3)]
+    );
+}
+
+#[test]
+fn tracks_and_lane_pitches_can_be_picked() {
+    let options = MidiImportOptions { track: Some(1), lane_pitches: Some([(36, 36), (38, 38), (46, 46), (42, 42)]), ..Default::default() };
+    let chart = midi::parse_midi(&song(), &options).unwrap();
+    assert_eq!(notes(&chart)[2..4], [(Beat::whole(2), 3), (Beat::whole(3), 2)]);
+
+    let options = MidiImportOptions { track: Some(2), ..Default::default() };
+    let chart = midi::parse_midi(&song(), &options).unwrap();
+    assert_eq!(chart.metadata.difficulty, "Bass");
+    assert_eq!(notes(&chart), vec![(Beat::ZERO, 0)]);
+
+    let options = MidiImportOptions { track: Some(3), ..Default::default() };
+    assert!(matches!(midi::parse_midi(&song(), &options), Err(ChartError::Parse(_))));
+}
@@ -0,0 +1,105 @@
+// osu!mania import: columns from x, hold ends, inherited timing points and other key counts
+
+use rusty_rhythm::charts::{osu, ChartError};
+use rusty_rhythm::*;
+
+fn beatmap(keys: u32, timing_points: &str, hit_objects: &str) -> String {
+    format!(
+        "osu file format v14
+
+[General]
+AudioFilename: audio.mp3
+Mode: 3
+
+[Metadata]
+Title:Import
+Artist:Rusty Rhythm
+Creator:Tester
+Version:{keys}K Hard
+
+[Difficulty]
+CircleSize:{keys}
+
+[TimingPoints]
+{timing_points}
+[HitObjects]
+{hit_objects}"
+    )
+}
+
+// 120 BPM, 2x speed from beat 2, back to 1x on beat 4, 240 BPM from beat 6
+const TIMING_POINTS: &str = "0,500,4,1,0,100,1,0
+1000,-50,4,1,0,100,0,0
+2000,-100,4,1,0,100,0,0
+3000,250,4,1,0,100,1,0
+";
+
+fn notes(chart: &Chart) -> Vec<(Beat, usize, Option<Beat>)> {
+    chart.notes.iter().map(|note| (note.beat, note.lane, note.end_beat)).collect()
+}
+
+#[test]
+fn columns_come_from_x_and_holds_keep_their_end() {
+    let hit_objects = "64,192,0,1,0,0:0:0:0:
+192,192,500,1,0,0:0:0:0:
+320,192,1000,128,0,2000:0:0:0:0:
+448,192,1500,1,0,0:0:0:0:
+500,192,3125,1,0,0:0:0:0:
+";
+    let chart = osu::parse_osu(&beatmap(4, TIMING_POINTS, hit_objects)).unwrap();
+    assert_eq!(chart.metadata.difficulty, "4K Hard");
+    assert_eq!(chart.audio.as_deref(), Some("audio.mp3"));
+    assert_eq!(
+        notes(&chart),
+        vec![
+            (Beat::ZERO, 0, None),
+            (Beat::whole(1), 1, None),
+            (Beat::whole(2), 2, Some(Beat::whole(4))),
+            (Beat::whole(3), 3, None),
+            (Beat::new(13, 2), 3, None),
+        ]
+    );
+}
+
+#[test]
+fn inherited_points_become_scroll_velocities() {
+    let chart = osu::parse_osu(&beatmap(4, TIMING_POINTS, "64,192,0,1,0,0:0:0:0:\n")).unwrap();
+    assert_eq!(chart.bpm, 120.0);
+    assert_eq!(chart.bpm_changes, vec![BpmChange { beat: Beat::whole(6), bpm: 240.0 }]);
+    assert_eq!(
+        chart.scroll_velocities,
+        vec![
+            ScrollVelocity { beat: Beat::whole(2), multiplier: 2.0 },
+            ScrollVelocity { beat: Beat::whole(4), multiplier: 1.0 },
+        ]
+    );
+}
+
+#[test]
+fn other_key_counts_fold_onto_the_lanes() {
+    // 7K columns 0-6 land on lanes 0 0 1 1 2 2 3
+    let hit_objects = "36,192,0,1,0,0:0:0:0:
+109,192,0,1,0,0:0:0:0:
+475,192,500,1,0,0:0:0:0:
+182,192,1000,128,0,2000:0:0:0:0:
+256,192,1500,1,0,0:0:0:0:
+329,192,1500,1,0,0:0:0:0:
+";
+    let chart = osu::parse_osu(&beatmap(7, TIMING_POINTS, hit_objects)).unwrap();
+    // Column 1 shares lane 0 with column 0, column 3 lands inside the hold on lane 1
+    assert_eq!(
+        notes(&chart),
+        vec![
+            (Beat::ZERO, 0, None),
+            (Beat::whole(1), 3, None),
+            (Beat::whole(2), 1, Some(Beat::whole(4))),
+            (Beat::whole(3), 2, None),
+        ]
+    );
+}
+
+#[test]
+fn only_mania_beatmaps_load() {
+    let standard = beatmap(4, TIMING_POINTS, "64,192,0,1,0,0:0:0:0:\n").replace("Mode: 3", "Mode: 0");
+    assert!(matches!(osu::parse_osu(&standard), Err(ChartError::Parse(_))));
+}
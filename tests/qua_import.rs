@@ -0,0 +1,78 @@
+// Quaver .qua import: lanes, holds, timing points and slider velocities
+
+use rusty_rhythm::charts::{qua, ChartError};
+use rusty_rhythm::*;
+
+const QUA: &str = "AudioFile: audio.mp3
+Mode: Keys4
+Title: Quaver Test
+Artist: Rusty Rhythm
+Creator: Tester
+DifficultyName: Hard
+TimingPoints:
+- Bpm: 120
+- StartTime: 2000
+  Bpm: 180
+  Signature: Triple
+SliderVelocities:
+- Multiplier: 1
+- StartTime: 1000
+  Multiplier: 2
+HitObjects:
+- Lane: 1
+- StartTime: 500
+  Lane: 4
+- StartTime: 1000
+  Lane: 2
+  EndTime: 1500
+- StartTime: 2333
+  Lane: 3
+";
+
+#[test]
+fn lanes_holds_and_metadata_come_through() {
+    let chart = qua::parse_qua(QUA).unwrap();
+    assert_eq!(chart.metadata.title, "Quaver Test");
+    assert_eq!(chart.metadata.charter, "Tester");
+    assert_eq!(chart.metadata.difficulty, "Hard");
+    assert_eq!(chart.audio.as_deref(), Some("audio.mp3"));
+
+    let notes: Vec<_> = chart.notes.iter().map(|note| (note.beat, note.lane, note.end_beat)).collect();
+    assert_eq!(
+        notes,
+        vec![
+            (Beat::ZERO, 0, None),
+            (Beat::whole(1), 3, None),
+            (Beat::whole(2), 1, Some(Beat::whole(3))),
+            (Beat::whole(5), 2, None),
+        ]
+    );
+}
+
+#[test]
+fn timing_points_become_bpm_changes_and_time_signatures() {
+    let chart = qua::parse_qua(QUA).unwrap();
+    assert_eq!(chart.offset, 0.0);
+    assert_eq!(chart.bpm, 120.0);
+    assert_eq!(chart.bpm_changes.len(), 1);
+    assert_eq!(chart.bpm_changes[0].beat, Beat::whole(4));
+    assert_eq!(chart.bpm_changes[0].bpm, 180.0);
+    assert_eq!(chart.time_signatures.len(), 1);
+    assert_eq!(chart.time_signatures[0].beat, Beat::whole(4));
+    assert_eq!(chart.time_signatures[0].numerator, 3);
+}
+
+#[test]
+fn only_slider_velocities_that_change_the_speed_are_kept() {
+    let chart = qua::parse_qua(QUA).unwrap();
+    // The 1.0x at the start matches the default speed
+    assert_eq!(chart.scroll_velocities.len(), 1);
+    assert_eq!(chart.scroll_velocities[0].beat, Beat::whole(2));
+    assert_eq!(chart.scroll_velocities[0].multiplier, 2.0);
+}
+
+#[test]
+fn other_modes_are_rejected() {
+    let keys7 = QUA.replace("Mode: Keys4", "Mode: Keys7");
+    assert!(matches!(qua::parse_qua(&keys7), Err(ChartError::Parse(_))));
+}
@@ -0,0 +1,72 @@
+// Every scoring system on a flawless run and on one with a single miss
+
+use rusty_rhythm::*;
+
+// Plays `total` judgements through one system, `None` is a miss
+fn play(kind: ScoringKind, total: u32, judgement: impl Fn(u32) -> Option<usize>) -> u64 {
+    let windows = JudgementWindows::standard();
+    let mut system = kind.system(total);
+    let mut combo = 0;
+    for index in 0..total {
+        match judgement(index) {
+            Some(tier) => {
+                combo += 1;
+                system.hit(tier, combo, &windows);
+            }
+            None => {
+                combo = 0;
+                system.miss();
+            }
+        }
+    }
+    system.score()
+}
+
+fn flawless(kind: ScoringKind) -> u64 {
+    play(kind, 40, |_| Some(0))
+}
+
+// The 21st judgement is missed
+fn one_miss(kind: ScoringKind) -> u64 {
+    play(kind, 40, |index| if index == 20 { None } else { Some(0) })
+}
+
+#[test]
+fn points_add_up_the_tier_scores() {
+    assert_eq!(flawless(ScoringKind::Points), 4000);
+    assert_eq!(one_miss(ScoringKind::Points), 3900);
+    assert_eq!(play(ScoringKind::Points, 3, |index| Some(index as usize)), 170);
+
+    let mut points = ScoringKind::Points.system(1);
+    points.mine();
+    assert_eq!(points.score(), 0);
+}
+
+#[test]
+fn million_is_out_of_a_million_whatever_the_length() {
+    assert_eq!(flawless(ScoringKind::Million), MILLION_SCORE_MAX);
+    assert_eq!(play(ScoringKind::Million, 7, |_| Some(0)), MILLION_SCORE_MAX);
+    assert_eq!(one_miss(ScoringKind::Million), 975_000);
+    // A GOOD is worth half a PERFECT
+    assert_eq!(play(ScoringKind::Million, 4, |index| Some((index == 0) as usize)), 875_000);
+    assert_eq!(ScoringKind::Million.system(0).score(), 0);
+}
+
+#[test]
+fn ex_scores_three_for_the_best_tier() {
+    assert_eq!(flawless(ScoringKind::Ex), 40 * EX_SCORE_BEST);
+    assert_eq!(one_miss(ScoringKind::Ex), 39 * EX_SCORE_BEST);
+    assert_eq!(play(ScoringKind::Ex, 3, |index| Some(index as usize)), 3 + 2 + 1);
+}
+
+#[test]
+fn arcade_multiplies_by_the_combo() {
+    // Combos 1-9 are worth 1x, 10-19 2x, 20-29 3x, then 4x from 30 on
+    assert_eq!(flawless(ScoringKind::Arcade), 100 * (9 + 2 * 10 + 3 * 10 + 4 * 11));
+    // Combo 20 just reached 3x, then the miss drops back to 1x for the next nine
+    assert_eq!(one_miss(ScoringKind::Arcade), 100 * (9 + 2 * 10 + 3 + 9 + 2 * 10));
+
+    let mut arcade = ScoringKind::Arcade.system(1);
+    arcade.mine();
+    assert_eq!(arcade.score(), 0);
+}
@@ -0,0 +1,118 @@
+// StepMania simfile parsing details that the round trips don't cover
+
+use rusty_rhythm::charts::{sm, ChartError};
+use rusty_rhythm::*;
+
+#[test]
+fn comments_are_stripped_but_urls_in_values_survive() {
+    let simfile = "// Exported by hand
+#TITLE:Link http://example.com/song;
+#ARTIST:Someone // the artist
+#CREDIT:https://charts.example.com;
+#BPMS:0.000=120.000;
+#NOTES:
+     dance-single:
+     :
+     Hard:
+     3:
+     0,0,0,0,0:
+1000 // first note
+0100
+0010
+0001
+,  // measure 2
+1000
+0000
+0000
+0000
+;
+";
+    let chart = sm::parse_sm(simfile).unwrap().remove(0);
+    assert_eq!(chart.metadata.title, "Link http://example.com/song");
+    assert_eq!(chart.metadata.artist, "Someone");
+    assert_eq!(chart.metadata.charter, "https://charts.example.com");
+    assert_eq!(chart.notes.len(), 5);
+}
+
+const SSC: &str = "#VERSION:0.83;
+#TITLE:Split Timing;
+#OFFSET:0.000;
+#BPMS:0.000=120.000;
+#NOTEDATA:;
+#STEPSTYPE:dance-single;
+#DIFFICULTY:Easy;
+#METER:2;
+#NOTES:
+1000
+0100
+0010
+0001
+;
+#NOTEDATA:;
+#STEPSTYPE:dance-double;
+#DIFFICULTY:Hard;
+#METER:8;
+#NOTES:
+10000000
+;
+#NOTEDATA:;
+#STEPSTYPE:dance-single;
+#DIFFICULTY:Hard;
+#METER:9;
+#OFFSET:-0.500;
+#BPMS:0.000=150.000,4.000=300.000;
+#STOPS:2.000=0.250;
+#NOTES:
+1000
+0000
+0001
+0000
+;
+";
+
+#[test]
+fn ssc_charts_can_bring_their_own_timing() {
+    let charts = sm::parse_sm(SSC).unwrap();
+    // The dance-double chart is skipped
+    assert_eq!(charts.len(), 2);
+
+    let easy = &charts[0];
+    assert_eq!((easy.metadata.difficulty.as_str(), easy.metadata.level), ("Easy", 2));
+    assert_eq!((easy.offset, easy.bpm), (0.0, 120.0));
+    assert!(easy.bpm_changes.is_empty() && easy.stops.is_empty());
+
+    let hard = &charts[1];
+    assert_eq!((hard.metadata.difficulty.as_str(), hard.metadata.level), ("Hard", 9));
+    assert_eq!((hard.offset, hard.bpm), (0.5, 150.0));
+    assert_eq!(hard.bpm_changes, vec![BpmChange { beat: Beat::whole(4), bpm: 300.0 }]);
+    assert_eq!(hard.stops, vec![Stop { beat: Beat::whole(2), duration: 0.25 }]);
+    assert_eq!(hard.metadata.title, "Split Timing");
+}
+
+fn simfile(notes: &str) -> String {
+    format!("#TITLE:Holds;\n#BPMS:0.000=120.000;\n#NOTES:\n     dance-single:\n     :\n     Hard:\n     5:\n     0,0,0,0,0:\n{};\n", notes)
+}
+
+#[test]
+fn holds_and_rolls_end_on_their_tail() {
+    let chart = sm::parse_sm(&simfile("2000\n0400\n3000\n0300\n,\n0020\n0000\n0030\n0000")).unwrap().remove(0);
+    let notes: Vec<_> = chart.notes.iter().map(|note| (note.beat, note.lane, note.kind, note.end_beat)).collect();
+    assert_eq!(
+        notes,
+        vec![
+            (Beat::ZERO, 0, NoteKind::Tap, Some(Beat::whole(2))),
+            (Beat::whole(1), 1, NoteKind::Roll, Some(Beat::whole(3))),
+            (Beat::whole(4), 2, NoteKind::Tap, Some(Beat::whole(6))),
+        ]
+    );
+}
+
+#[test]
+fn unmatched_hold_symbols_are_errors() {
+    // A head that never ends
+    assert!(matches!(sm::parse_sm(&simfile("2000\n0000\n1000\n0000")), Err(ChartError::Parse(_))));
+    // A tail with nothing to close
+    assert!(matches!(sm::parse_sm(&simfile("1000\n0000\n3000\n0000")), Err(ChartError::Parse(_))));
+    // A second head before the first one ends
+    assert!(matches!(sm::parse_sm(&simfile("2000\n0000\n4000\n3000")), Err(ChartError::Parse(_))));
+}
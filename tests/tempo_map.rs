@@ -0,0 +1,81 @@
+// Beat <-> time conversion across BPM changes, stops, delays and time signatures
+
+use rusty_rhythm::*;
+
+fn close(actual: f32, expected: f32) {
+    assert!((actual - expected).abs() < 1e-4, "expected {}, got {}", expected, actual);
+}
+
+#[test]
+fn bpm_changes_speed_up_the_beats_after_them() {
+    // 120 BPM for 4 beats, then 240
+    let changes = [BpmChange { beat: Beat::whole(4), bpm: 240.0 }];
+    let map = TempoMap::new(0.5, 120.0, &changes, &[], &[], &[]);
+
+    close(map.beat_to_seconds(Beat::ZERO), 0.5);
+    close(map.beat_to_seconds(Beat::whole(4)), 2.5);
+    close(map.beat_to_seconds(Beat::whole(6)), 3.0);
+    close(map.beat_to_seconds(Beat::new(1, 2)), 0.75);
+    assert_eq!(map.bpm_at(Beat::new(7, 2)), 120.0);
+    assert_eq!(map.bpm_at(Beat::whole(4)), 240.0);
+
+    close(map.seconds_to_beat(2.5), 4.0);
+    close(map.seconds_to_beat(3.0), 6.0);
+    // Before the offset the first tempo runs backwards into the lead-in
+    close(map.seconds_to_beat(0.0), -1.0);
+}
+
+#[test]
+fn stops_pause_after_their_beat() {
+    // One beat a second, frozen for a second on beat 2
+    let stops = [Stop { beat: Beat::whole(2), duration: 1.0 }];
+    let map = TempoMap::new(0.0, 60.0, &[], &stops, &[], &[]);
+
+    // A note on the stop's beat is hit when the stop starts
+    close(map.beat_to_seconds(Beat::whole(2)), 2.0);
+    close(map.beat_to_seconds(Beat::new(5, 2)), 3.5);
+    close(map.seconds_to_beat(2.5), 2.0);
+    close(map.seconds_to_beat(3.5), 2.5);
+}
+
+#[test]
+fn delays_pause_before_their_beat() {
+    let delays = [Delay { beat: Beat::whole(2), duration: 1.0 }];
+    let map = TempoMap::new(0.0, 60.0, &[], &[], &delays, &[]);
+
+    // A note on the delay's beat is hit when the delay ends
+    close(map.beat_to_seconds(Beat::whole(2)), 3.0);
+    close(map.beat_to_seconds(Beat::new(5, 2)), 3.5);
+    close(map.seconds_to_beat(2.5), 2.0);
+    close(map.seconds_to_beat(3.5), 2.5);
+}
+
+#[test]
+fn measures_are_counted_through_time_signature_changes() {
+    let signatures = [
+        TimeSignature { beat: Beat::whole(4), numerator: 3, denominator: 4 },
+        TimeSignature { beat: Beat::whole(10), numerator: 6, denominator: 8 },
+    ];
+    let map = TempoMap::new(0.0, 120.0, &[], &[], &[], &signatures);
+
+    assert_eq!(map.measure_position(Beat::whole(-1)), (-1, Beat::whole(3)));
+    assert_eq!(map.measure_position(Beat::whole(3)), (0, Beat::whole(3)));
+    assert_eq!(map.measure_position(Beat::whole(5)), (1, Beat::whole(1)));
+    assert_eq!(map.measure_position(Beat::new(19, 2)), (2, Beat::new(5, 2)));
+    assert_eq!(map.measure_position(Beat::whole(11)), (3, Beat::whole(1)));
+    assert_eq!(map.time_signature_at(Beat::whole(8)).numerator, 3);
+
+    // 6/8 clicks on every eighth
+    assert_eq!(map.next_click(Beat::whole(10)), Beat::new(21, 2));
+    assert_eq!(map.next_click(Beat::new(7, 2)), Beat::whole(4));
+}
+
+#[test]
+fn a_signature_mid_measure_cuts_it_short() {
+    let signatures = [TimeSignature { beat: Beat::whole(2), numerator: 3, denominator: 4 }];
+    let map = TempoMap::new(0.0, 120.0, &[], &[], &[], &signatures);
+
+    assert_eq!(map.measure_position(Beat::whole(1)), (0, Beat::whole(1)));
+    assert_eq!(map.measure_position(Beat::whole(2)), (1, Beat::ZERO));
+    assert_eq!(map.measure_position(Beat::whole(5)), (2, Beat::ZERO));
+}